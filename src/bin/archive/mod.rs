@@ -0,0 +1,223 @@
+use crate::args::{Format, Options, STDIO};
+use crate::commands::{backup, Failure, Outcome, Report};
+use crate::json::Value;
+use crate::walk::Input;
+use fest::{write_atomic, ArchiveEntry, FESData, SaveArchive};
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+const SHORT_HASH_LEN: usize = 12;
+
+pub fn add(input: &Input, file_data: &FESData, options: &Options) -> Result<Outcome, Failure> {
+    let archive = SaveArchive::open(options.archive_dir());
+
+    // NOTE: Restoring writes back where the save was added from, so that has to work from any directory
+    let source = match input.name.as_str() {
+        STDIO => STDIO.to_string(),
+        name => fs::canonicalize(name)
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or(name.to_string()),
+    };
+
+    let mut report = Report::new(file_data);
+
+    // NOTE: Dry runs still decompress and hash the save, so they tell whether it would be a new version
+    let (entry, added) = match options.dry_run {
+        true => archive.preview(file_data, &source)?,
+        false => archive.add(file_data, &source)?,
+    };
+
+    report.plain_size = Some(entry.plain_size);
+    report.message = match (added, options.dry_run) {
+        (true, false) => format!("Stored as {} of {}", short_hash(&entry), entry.slot_key()),
+        (true, true) => format!(
+            "Would store it as {} of {} into '{}'",
+            short_hash(&entry),
+            entry.slot_key(),
+            archive.root().display()
+        ),
+        (false, _) => format!(
+            "Already stored as the latest {} of {}",
+            short_hash(&entry),
+            entry.slot_key()
+        ),
+    };
+
+    Ok(Outcome::Done(report))
+}
+
+pub fn restore(input: &Input, options: &Options) -> Result<Outcome, Failure> {
+    let archive = SaveArchive::open(options.archive_dir());
+    let entry = archive.find(&input.name)?;
+    let file_data = archive.restore(&entry)?;
+
+    let mut report = Report::new(&file_data);
+    report.checksum_valid = Some(true).filter(|_| file_data.is_compressed);
+
+    if options.writes_stdout() {
+        if options.dry_run {
+            report.message = "Would write to stdout".to_string();
+            return Ok(Outcome::Done(report));
+        }
+
+        let mut stdout = io::stdout().lock();
+        stdout
+            .write_all(&file_data.raw)
+            .and_then(|_| stdout.flush())
+            .map_err(|e| Failure::new("write_error", e.to_string()))?;
+
+        report.output = Some(STDIO.to_string());
+        report.message = "Wrote to stdout".to_string();
+        return Ok(Outcome::Done(report));
+    }
+
+    let output_path = match (&options.output, entry.source.as_str()) {
+        (Some(output), _) => PathBuf::from(output),
+        (None, STDIO) => {
+            return Err(Failure::new(
+                "invalid_output",
+                "It was added from stdin, give where to restore it with '--output'".to_string(),
+            ))
+        }
+        (None, source) => PathBuf::from(source),
+    };
+    report.output = Some(output_path.to_string_lossy().to_string());
+
+    if options.dry_run {
+        report.message = match (output_path.exists(), options.force) {
+            (false, _) => format!("Would restore it into '{}'", output_path.display()),
+            (true, false) => {
+                return Err(Failure::new(
+                    "file_exists",
+                    format!(
+                        "Would not overwrite '{}', it already exists",
+                        output_path.display()
+                    ),
+                ))
+            }
+            (true, true) => format!("Would restore it over '{}'", output_path.display()),
+        };
+        return Ok(Outcome::Done(report));
+    }
+
+    if let Some(parent) = output_path.parent().filter(|parent| !parent.exists()) {
+        fs::create_dir_all(parent).map_err(|e| Failure::new("write_error", e.to_string()))?;
+    }
+
+    report.backup = match options.force && options.backup {
+        true => backup(&output_path, options)?,
+        false => None,
+    };
+
+    write_atomic(&output_path, &file_data.raw, options.write_options())?;
+
+    report.message = match &report.backup {
+        Some(backup_path) => format!(
+            "Restored {} of {} into '{}', backed up the previous one to '{}'",
+            short_hash(&entry),
+            entry.slot_key(),
+            output_path.display(),
+            backup_path.display()
+        ),
+        None => format!(
+            "Restored {} of {} into '{}'",
+            short_hash(&entry),
+            entry.slot_key(),
+            output_path.display()
+        ),
+    };
+
+    Ok(Outcome::Done(report))
+}
+
+// NOTE: Returns how many versions were listed, slots given as files only list theirs, for every game unless it is given too (Like "Awakening Chapter1")
+pub fn list(options: &Options) -> Result<usize, String> {
+    let archive = SaveArchive::open(options.archive_dir());
+    let history = archive
+        .history()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|entry| {
+            options.files.is_empty()
+                || options.files.iter().any(|slot| {
+                    slot.eq_ignore_ascii_case(&entry.slot_name())
+                        || slot.eq_ignore_ascii_case(&entry.slot_key())
+                })
+        })
+        .collect::<Vec<ArchiveEntry>>();
+
+    let mut slots = history
+        .iter()
+        .map(|entry| entry.slot_key())
+        .collect::<Vec<String>>();
+    slots.sort();
+    slots.dedup();
+
+    for slot in &slots {
+        let versions = history.iter().filter(|entry| &entry.slot_key() == slot);
+
+        match options.format {
+            Format::Text => {
+                println!("{}:", slot);
+                for entry in versions {
+                    println!("  {}", line(entry));
+                }
+            }
+            Format::Json => {
+                for entry in versions {
+                    println!("{}", record(entry));
+                }
+            }
+        }
+    }
+
+    Ok(history.len())
+}
+
+fn short_hash(entry: &ArchiveEntry) -> &str {
+    &entry.hash[..SHORT_HASH_LEN]
+}
+
+fn line(entry: &ArchiveEntry) -> String {
+    let state = match entry.is_compressed {
+        true => "compressed",
+        false => "decompressed",
+    };
+    let source = Path::new(&entry.source)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or(entry.source.to_string());
+
+    format!(
+        "{} {} {}, {} bytes, from '{}'",
+        short_hash(entry),
+        entry.added,
+        state,
+        entry.plain_size,
+        source
+    )
+}
+
+fn record(entry: &ArchiveEntry) -> Value {
+    Value::Object(vec![
+        ("hash", entry.hash.as_str().into()),
+        ("added", entry.added.as_str().into()),
+        ("slot", entry.slot_name().into()),
+        ("kind", entry.kind.name().to_lowercase().into()),
+        (
+            "game",
+            entry
+                .variant
+                .map(|variant| variant.name().to_lowercase())
+                .into(),
+        ),
+        ("compressed", entry.is_compressed.into()),
+        ("comp_version", entry.comp_version.into()),
+        ("header_length", entry.header_len.into()),
+        ("plain_size", entry.plain_size.into()),
+        ("source", entry.source.as_str().into()),
+    ])
+}
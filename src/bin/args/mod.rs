@@ -0,0 +1,450 @@
+use crate::output::check_template;
+use fest::{GameVariant, SaveKind, WriteOptions};
+use std::thread;
+
+pub const USAGE: &str = r#"Usage: fest [COMMAND] [OPTIONS] <FILES | DIRECTORIES>...
+       fest [COMMAND] [OPTIONS] - [OUTPUT]
+       fest watch [OPTIONS] --out-dir MIRROR DIRECTORY
+       fest locate [OPTIONS] [DIRECTORIES]...
+       fest list|unpack|pack [OPTIONS] <FOLDERS>...
+       fest textconv [OPTIONS] FILE
+       fest diff [OPTIONS] FILE FILE
+       fest hexdump [OPTIONS] FILE
+       fest archive add [OPTIONS] <FILES | DIRECTORIES>...
+       fest archive list [OPTIONS] [SLOTS]...
+       fest archive restore [OPTIONS] HASH
+
+A single "-" reads from stdin and writes to OUTPUT, or to stdout if not given. Using "-" as
+the output of any single file also writes to stdout.
+
+Commands:
+  auto        Decompress compressed files and compress decompressed ones (Default)
+  decompress  Decompress files, failing on already decompressed ones
+  compress    Compress files, failing on already compressed ones
+  info        Print what a file is and what is inside it, without writing anything
+  verify      Check the checksum of compressed files, and that decompressed ones survive compression
+  watch       Keep decompressing the saves of DIRECTORY into MIRROR as they change, until stopped
+  locate      List the saves found in the emulator data DIRECTORIES, or in the usual places if none are given
+  list        Print the saves inside backup FOLDERS made by save managers like Checkpoint or JKSM
+  unpack      Decompress every save of backup FOLDERS into a sibling "_dec" folder, copying the other files
+  pack        Compress every save of unpacked FOLDERS back into a restorable folder, verifying all of them first
+  textconv    Print a stable text dump of FILE, with its headers and the bytes of every block, to
+              use as a "textconv" diff driver in git
+  diff        Print which bytes changed between two saves, compressed or not, block by block
+  hexdump     Print the decompressed data of FILE in hex, marking where each header, INDE entry and
+              block is and the value of every field read from them
+  archive     Keep every version of saves in an archive: "add" stores them, "list" prints the versions
+              stored for each slot (Like "Chapter1" or "Global"), and "restore" writes one back where it
+              was added from, or to "--output", given the start of its hash
+  help        Print this message
+
+Options:
+  --scan             Search the first bytes of the file for the magic instead of only at the usual offsets
+  --kind KIND        Treat files as this kind of save: global, chapter, map or backup
+  --game GAME        Treat files as saves of this game: awakening, fates or echoes
+  --comp-version N   Compress with this COMP version (Default: The one of the save being overwritten, or 2)
+  -r, --recursive    Process every file inside the given directories, skipping the ones that aren't saves
+  --include GLOB     Only process the files found in directories that match GLOB, can be given multiple times
+  --exclude GLOB     Don't process the files found in directories that match GLOB, can be given multiple times
+                     Globs without "/" match file names, otherwise the path inside the directory, where
+                     "*" and "?" match inside a single component and "**" matches any number of them
+  --format FORMAT    Print results as "text" or as "json", one object for each file (Default: text)
+  -j, --jobs N       Process N files at the same time, 0 uses one for each CPU (Default: 1)
+  -o, --output FILE  Write the result of the only given file to FILE
+  --out-dir DIR      Write results into DIR instead of next to each file, keeping the layout of directories
+  --name TEMPLATE    Name results following TEMPLATE, where {name} is the file name, {stem} and {ext} are
+                     the file name without and only its extension, and {mode} is either "dec" or "com"
+  -f, --force        Overwrite files that already exist
+  --no-clobber       Never overwrite files that already exist (Default)
+  -n, --dry-run      Do everything in memory and print what would be written where, without writing anything
+  --sync             Flush results to disk before moving them into place
+  --backup-dir DIR   Where files are backed up before being overwritten (Default: ".fest_backups" next to them)
+  --keep N           How many backups to keep for each file, 0 keeps all of them (Default: 10)
+  --no-backup        Don't back up files before overwriting them
+  -v, --verbose      Print what is being done, more times print more details (Up to "-vvv")
+  -q, --quiet        Only print errors
+  --recompress       When watching, also compress files edited in MIRROR back into DIRECTORY (Needs '--force')
+  --debounce MS      When watching, wait until files stop changing for MS milliseconds (Default: 500)
+  --color WHEN       When "hexdump" uses colours: "auto", "always" or "never" (Default: auto, only in a terminal)
+  --archive DIR      Where the archive is kept (Default: ".fest_archive" in the current directory)
+  -h, --help         Print this message
+
+Exit status:
+  0  Every file was processed
+  1  At least one file failed or was not found
+  2  The arguments couldn't be understood
+
+  'diff' exits with 0 when the saves are the same, 1 when they differ and 2 when they couldn't be compared"#;
+
+pub const STDIO: &str = "-";
+pub const DEFAULT_BACKUP_DIR: &str = ".fest_backups";
+pub const DEFAULT_ARCHIVE_DIR: &str = ".fest_archive";
+const DEFAULT_KEEP: usize = 10;
+const DEFAULT_DEBOUNCE: u64 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Auto,
+    Decompress,
+    Compress,
+    Info,
+    Verify,
+    Watch,
+    Locate,
+    List,
+    Unpack,
+    Pack,
+    Textconv,
+    Diff,
+    Hexdump,
+    ArchiveAdd,
+    ArchiveList,
+    ArchiveRestore,
+    Help,
+}
+
+impl Command {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(Command::Auto),
+            "decompress" => Some(Command::Decompress),
+            "compress" => Some(Command::Compress),
+            "info" => Some(Command::Info),
+            "verify" => Some(Command::Verify),
+            "watch" => Some(Command::Watch),
+            "locate" => Some(Command::Locate),
+            "list" => Some(Command::List),
+            "unpack" => Some(Command::Unpack),
+            "pack" => Some(Command::Pack),
+            "textconv" => Some(Command::Textconv),
+            "diff" => Some(Command::Diff),
+            "hexdump" => Some(Command::Hexdump),
+            "help" => Some(Command::Help),
+            _ => None,
+        }
+    }
+
+    fn from_archive_action(action: &str) -> Option<Self> {
+        match action {
+            "add" => Some(Command::ArchiveAdd),
+            "list" => Some(Command::ArchiveList),
+            "restore" => Some(Command::ArchiveRestore),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub command: Command,
+    pub files: Vec<String>,
+    pub scan: bool,
+    pub kind: Option<SaveKind>,
+    pub variant: Option<GameVariant>,
+    pub comp_version: Option<u32>,
+    pub recursive: bool,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub jobs: usize,
+    pub format: Format,
+    pub output: Option<String>,
+    pub out_dir: Option<String>,
+    pub name_template: Option<String>,
+    pub force: bool,
+    pub dry_run: bool,
+    pub sync: bool,
+    pub backup: bool,
+    pub backup_dir: Option<String>,
+    pub keep: usize,
+    pub recompress: bool,
+    pub debounce: u64,
+    pub archive_dir: Option<String>,
+    pub color: Color,
+    pub verbosity: i8,
+}
+
+impl Options {
+    // NOTE: Expects the arguments without the executable's name, and a missing command means "auto" so files can still be dropped on the executable
+    pub fn parse<I>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+
+        let mut options = Options {
+            command: Command::Auto,
+            files: vec![],
+            scan: false,
+            kind: None,
+            variant: None,
+            comp_version: None,
+            recursive: false,
+            include: vec![],
+            exclude: vec![],
+            jobs: 1,
+            format: Format::Text,
+            output: None,
+            out_dir: None,
+            name_template: None,
+            force: false,
+            dry_run: false,
+            sync: false,
+            backup: true,
+            backup_dir: None,
+            keep: DEFAULT_KEEP,
+            recompress: false,
+            debounce: DEFAULT_DEBOUNCE,
+            archive_dir: None,
+            color: Color::Auto,
+            verbosity: 0,
+        };
+
+        let mut only_files = false;
+        let mut command = None;
+        let mut help = false;
+        while let Some(arg) = args.next() {
+            if only_files || !arg.starts_with('-') || arg == STDIO {
+                // NOTE: The command is the first argument that isn't an option, so options can come before it too
+                if command.is_none() && !only_files && options.files.is_empty() {
+                    if arg == "archive" {
+                        let action = args.next().unwrap_or_default();
+                        command = Some(
+                            Command::from_archive_action(&action)
+                                .ok_or(format!("Unknown archive command '{}'", action))?,
+                        );
+                        continue;
+                    }
+
+                    if let Some(name) = Command::from_name(&arg) {
+                        command = Some(name);
+                        continue;
+                    }
+                }
+
+                options.files.push(arg);
+                continue;
+            }
+
+            match arg.as_str() {
+                "--" => only_files = true,
+                "-h" | "--help" => help = true,
+                "-v" | "--verbose" => options.verbosity = options.verbosity.max(0) + 1,
+                "-q" | "--quiet" => options.verbosity = -1,
+                _ if arg.len() > 2 && arg[1..].chars().all(|c| c == 'v') => {
+                    options.verbosity = options.verbosity.max(0) + (arg.len() - 1) as i8
+                }
+                "--scan" => options.scan = true,
+                "--kind" => {
+                    let value = next_value(&mut args, &arg)?;
+                    options.kind = Some(value.parse().map_err(|e| format!("{}", e))?);
+                }
+                "--game" => {
+                    let value = next_value(&mut args, &arg)?;
+                    options.variant = Some(value.parse().map_err(|e| format!("{}", e))?);
+                }
+                "-r" | "--recursive" => options.recursive = true,
+                "--include" => options.include.push(next_value(&mut args, &arg)?),
+                "--exclude" => options.exclude.push(next_value(&mut args, &arg)?),
+                "-j" | "--jobs" => {
+                    let value = next_value(&mut args, &arg)?;
+                    options.jobs = match value.parse() {
+                        Ok(0) => thread::available_parallelism()
+                            .map(|jobs| jobs.get())
+                            .unwrap_or(1),
+                        Ok(jobs) => jobs,
+                        Err(_) => return Err(format!("Invalid number of jobs '{}'", value)),
+                    };
+                }
+                "--format" => {
+                    let value = next_value(&mut args, &arg)?;
+                    options.format = match value.as_str() {
+                        "text" => Format::Text,
+                        "json" => Format::Json,
+                        _ => return Err(format!("Unknown format '{}'", value)),
+                    };
+                }
+                "-o" | "--output" => options.output = Some(next_value(&mut args, &arg)?),
+                "--out-dir" => options.out_dir = Some(next_value(&mut args, &arg)?),
+                "--name" => {
+                    let template = next_value(&mut args, &arg)?;
+                    check_template(&template)?;
+
+                    options.name_template = Some(template);
+                }
+                "-f" | "--force" => options.force = true,
+                "--no-clobber" => options.force = false,
+                "-n" | "--dry-run" => options.dry_run = true,
+                "--sync" => options.sync = true,
+                "--backup-dir" => options.backup_dir = Some(next_value(&mut args, &arg)?),
+                "--keep" => {
+                    let value = next_value(&mut args, &arg)?;
+                    options.keep = value
+                        .parse()
+                        .map_err(|_| format!("Invalid number of backups '{}'", value))?;
+                }
+                "--comp-version" => {
+                    let value = next_value(&mut args, &arg)?;
+                    options.comp_version = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Invalid COMP version '{}'", value))?,
+                    );
+                }
+                "--no-backup" => options.backup = false,
+                "--recompress" => options.recompress = true,
+                "--debounce" => {
+                    let value = next_value(&mut args, &arg)?;
+                    options.debounce = value
+                        .parse()
+                        .map_err(|_| format!("Invalid debounce '{}'", value))?;
+                }
+                "--color" => {
+                    let value = next_value(&mut args, &arg)?;
+                    options.color = match value.as_str() {
+                        "auto" => Color::Auto,
+                        "always" => Color::Always,
+                        "never" => Color::Never,
+                        _ => return Err(format!("Unknown color choice '{}'", value)),
+                    };
+                }
+                "--archive" => options.archive_dir = Some(next_value(&mut args, &arg)?),
+                _ => return Err(format!("Unknown option '{}'", arg)),
+            }
+        }
+
+        options.command = match help {
+            true => Command::Help,
+            false => command.unwrap_or(Command::Auto),
+        };
+
+        if options.files.is_empty()
+            && !matches!(
+                options.command,
+                Command::Help | Command::Locate | Command::ArchiveList
+            )
+        {
+            return Err("No files given".to_string());
+        }
+
+        if options.files.first().is_some_and(|file| file == STDIO) && options.files.len() == 2 {
+            if options.output.is_some() {
+                return Err("Output given both as '--output' and after '-'".to_string());
+            }
+
+            options.output = options.files.pop();
+        }
+
+        if options.files.len() > 1 && options.files.iter().any(|file| file == STDIO) {
+            return Err("'-' can only be used as the only input".to_string());
+        }
+
+        if options.command == Command::Watch {
+            if options.files.len() != 1 || options.files.iter().any(|file| file == STDIO) {
+                return Err("Command 'watch' needs a single directory".to_string());
+            }
+
+            if options.out_dir.is_none() || options.output.is_some() {
+                return Err(
+                    "Command 'watch' needs '--out-dir' for the mirror directory".to_string()
+                );
+            }
+
+            // NOTE: Every edit in the mirror replaces a save, which is only done when asked for
+            if options.recompress && !options.force {
+                return Err(
+                    "'--recompress' overwrites the watched saves, it needs '--force'".to_string(),
+                );
+            }
+        }
+
+        if matches!(options.command, Command::Textconv | Command::Hexdump)
+            && options.files.len() != 1
+        {
+            return Err("Commands 'textconv' and 'hexdump' need a single file".to_string());
+        }
+
+        if options.command == Command::Diff && options.files.len() != 2 {
+            return Err("Command 'diff' needs two files".to_string());
+        }
+
+        if options.command == Command::ArchiveRestore && options.files.len() != 1 {
+            return Err("Command 'archive restore' needs a single hash".to_string());
+        }
+
+        if options.takes_folders() && options.files.iter().any(|file| file == STDIO) {
+            return Err("Backup folders can't be read from stdin".to_string());
+        }
+
+        if options.output.is_some() {
+            if options.files.len() > 1 {
+                return Err("Option '--output' only works with a single file".to_string());
+            }
+
+            if options.out_dir.is_some() || options.name_template.is_some() {
+                return Err(
+                    "Option '--output' can't be used with '--out-dir' or '--name'".to_string(),
+                );
+            }
+        }
+
+        Ok(options)
+    }
+
+    // NOTE: Backup folders are restored as a whole, so these commands take folders instead of the files inside them
+    pub fn takes_folders(&self) -> bool {
+        matches!(
+            self.command,
+            Command::List | Command::Unpack | Command::Pack
+        )
+    }
+
+    pub fn writes_stdout(&self) -> bool {
+        if !matches!(
+            self.command,
+            Command::Auto | Command::Decompress | Command::Compress | Command::ArchiveRestore
+        ) {
+            return false;
+        }
+
+        match &self.output {
+            Some(output) => output == STDIO,
+            None => self.files.iter().any(|file| file == STDIO),
+        }
+    }
+
+    pub fn is_quiet(&self) -> bool {
+        self.verbosity < 0
+    }
+
+    pub fn archive_dir(&self) -> &str {
+        self.archive_dir.as_deref().unwrap_or(DEFAULT_ARCHIVE_DIR)
+    }
+
+    pub fn write_options(&self) -> WriteOptions {
+        WriteOptions {
+            no_clobber: !self.force,
+            sync: self.sync,
+        }
+    }
+}
+
+fn next_value<I>(args: &mut I, option: &str) -> Result<String, String>
+where
+    I: Iterator<Item = String>,
+{
+    args.next()
+        .ok_or(format!("Option '{}' needs a value", option))
+}
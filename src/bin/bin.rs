@@ -1,45 +1,267 @@
-use std::{env, fs::File, io::Read, path::Path};
+mod archive;
+mod args;
+mod commands;
+mod diff;
+mod hexdump;
+mod info;
+mod json;
+mod locate;
+mod logger;
+mod output;
+mod pool;
+mod textconv;
+mod walk;
+mod watch;
 
-use fest::FESData;
+use args::{Command, Format, Options, USAGE};
+use commands::{process_file, Failure, Outcome, Report};
+use diff::diff;
+use hexdump::hexdump;
+use json::Value;
+use locate::locate;
+use pool::run_ordered;
+use std::{env, process};
+use textconv::textconv;
+use walk::{collect_folders, collect_inputs, Input};
+use watch::watch;
 
-fn process_file(file_name: &str) -> Result<(), String> {
-    let mut file_name = file_name.to_string();
-    let mut file = File::open(&file_name).map_err(|e| e.to_string())?;
-    let mut raw = vec![];
+const EXIT_FAILED_FILES: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+// NOTE: Like "diff", finding differences isn't a failure, so failing to compare is told apart from it
+const EXIT_DIFFERENT: i32 = 1;
+const EXIT_DIFF_FAILED: i32 = 2;
 
-    file.read_to_end(&mut raw).map_err(|e| e.to_string())?;
+fn main() {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}, run 'fest help' to see the usage", error);
+            process::exit(EXIT_USAGE);
+        }
+    };
 
-    let mut file_data = FESData::process_data(&raw).map_err(|e| e.to_string())?;
+    logger::init(options.verbosity);
 
-    if file_data.is_compressed {
-        file_name = format!("{}_dec", file_name);
+    if options.command == Command::Help {
+        println!("{}", USAGE);
+        return;
+    }
 
-        file_data = file_data.decompress().map_err(|e| e.to_string())?;
-    } else {
-        file_name = if file_name.ends_with("_dec") {
-            file_name.trim_end_matches("_dec").to_string()
-        } else {
-            format!("{}_com", file_name)
+    if options.command == Command::Locate {
+        match locate(&options) {
+            Ok(0) => {
+                eprintln!("No saves found");
+                process::exit(EXIT_FAILED_FILES);
+            }
+            Ok(_) => return,
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(EXIT_FAILED_FILES);
+            }
+        }
+    }
+
+    if matches!(options.command, Command::Textconv | Command::Hexdump) {
+        let result = match options.command {
+            Command::Textconv => textconv(&options),
+            _ => hexdump(&options),
         };
 
-        file_data = file_data.compress().map_err(|e| e.to_string())?;
+        if let Err(error) = result {
+            eprintln!("{}", error);
+            process::exit(EXIT_FAILED_FILES);
+        }
+
+        return;
     }
 
-    file_data.write_to(&file_name).map_err(|e| e.to_string())?;
+    if options.command == Command::Diff {
+        match diff(&options) {
+            Ok(0) => {
+                if !options.is_quiet() {
+                    eprintln!("Saves are the same");
+                }
 
-    Ok(())
-}
+                return;
+            }
+            Ok(_) => process::exit(EXIT_DIFFERENT),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(EXIT_DIFF_FAILED);
+            }
+        }
+    }
 
-fn main() {
-    let args = env::args()
-        .filter(|e| Path::new(e).exists())
-        .collect::<Vec<String>>();
+    if options.command == Command::ArchiveList {
+        match archive::list(&options) {
+            Ok(0) => {
+                eprintln!("No versions found");
+                process::exit(EXIT_FAILED_FILES);
+            }
+            Ok(_) => return,
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(EXIT_FAILED_FILES);
+            }
+        }
+    }
+
+    // NOTE: Watching finds its own files as they change, and restoring takes hashes instead of files
+    let (inputs, errors) = match options.command {
+        Command::Watch => (vec![], vec![]),
+        Command::ArchiveRestore => (
+            options
+                .files
+                .iter()
+                .map(|hash| Input {
+                    name: hash.to_string(),
+                    relative: None,
+                })
+                .collect(),
+            vec![],
+        ),
+        _ if options.takes_folders() => collect_folders(&options),
+        _ => collect_inputs(&options),
+    };
+
+    let (mut processed, mut skipped, mut failed) = (0, 0, 0);
+
+    // NOTE: When the result goes to stdout, messages have to go elsewhere to not get mixed with it
+    let print = |line: String| match options.writes_stdout() {
+        true => eprintln!("{}", line),
+        false => println!("{}", line),
+    };
 
-    for arg in args {
-        if let Err(error) = process_file(&arg) {
-            eprintln!("Error ocurred when reading '{0}': '{1}'", arg, error)
-        } else {
-            println!("Finished processing of '{0}'", arg)
+    for (file, error) in &errors {
+        match options.format {
+            Format::Text => eprintln!(
+                "Error ocurred when reading '{0}': '{1}'",
+                file, error.message
+            ),
+            Format::Json => print(record(file, Err(error)).to_string()),
         }
+        failed += 1;
+    }
+
+    let report = |input: &Input, result: Result<Outcome, Failure>| {
+        match &result {
+            Ok(Outcome::Done(_)) => processed += 1,
+            Ok(Outcome::Skipped(_)) => skipped += 1,
+            Err(_) => failed += 1,
+        }
+
+        match (options.format, result) {
+            (Format::Json, result) => print(record(&input.name, result.as_ref()).to_string()),
+            (
+                Format::Text,
+                Ok(Outcome::Done(Report {
+                    info: Some(info), ..
+                })),
+            ) => print(format!("'{0}'\n{1}", input.name, info)),
+            (Format::Text, Ok(_)) if options.is_quiet() => {}
+            (Format::Text, Ok(Outcome::Done(report))) => print(format!(
+                "Finished processing of '{0}': {1}",
+                input.name, report.message
+            )),
+            (Format::Text, Ok(Outcome::Skipped(reason))) => {
+                eprintln!("Skipped '{0}': '{1}'", input.name, reason.message)
+            }
+            (Format::Text, Err(error)) => eprintln!(
+                "Error ocurred when reading '{0}': '{1}'",
+                input.name, error.message
+            ),
+        }
+    };
+
+    if options.command == Command::Watch {
+        if let Err(error) = watch(&options, report) {
+            eprintln!("{}", error);
+            process::exit(EXIT_FAILED_FILES);
+        }
+
+        return;
+    }
+
+    run_ordered(
+        &inputs,
+        options.jobs,
+        |input| process_file(input, &options),
+        report,
+    );
+
+    if !options.is_quiet() {
+        eprintln!(
+            "Processed {} {}, skipped {} and {} failed",
+            processed,
+            match options.takes_folders() {
+                true => "folders",
+                false => "files",
+            },
+            skipped,
+            failed
+        );
+    }
+
+    if failed > 0 {
+        process::exit(EXIT_FAILED_FILES);
+    }
+}
+
+fn record(input: &str, result: Result<&Outcome, &Failure>) -> Value {
+    let error = |failure: &Failure| {
+        Value::Object(vec![
+            ("code", failure.code.into()),
+            ("message", failure.message.as_str().into()),
+        ])
+    };
+
+    match result {
+        Ok(Outcome::Done(report)) => Value::Object(vec![
+            ("input", input.into()),
+            ("status", "ok".into()),
+            (
+                "kind",
+                report.kind.map(|kind| kind.name().to_lowercase()).into(),
+            ),
+            (
+                "game",
+                report
+                    .variant
+                    .map(|variant| variant.name().to_lowercase())
+                    .into(),
+            ),
+            ("compressed_size", report.compressed_size.into()),
+            ("plain_size", report.plain_size.into()),
+            ("checksum_valid", report.checksum_valid.into()),
+            ("output", report.output.clone().into()),
+            (
+                "backup",
+                report
+                    .backup
+                    .as_ref()
+                    .map(|backup| backup.to_string_lossy().to_string())
+                    .into(),
+            ),
+            (
+                "info",
+                report
+                    .info
+                    .as_ref()
+                    .map(|info| info.to_json())
+                    .unwrap_or(Value::Null),
+            ),
+            ("message", report.message.as_str().into()),
+            ("error", Value::Null),
+        ]),
+        Ok(Outcome::Skipped(reason)) => Value::Object(vec![
+            ("input", input.into()),
+            ("status", "skipped".into()),
+            ("error", error(reason)),
+        ]),
+        Err(failure) => Value::Object(vec![
+            ("input", input.into()),
+            ("status", "error".into()),
+            ("error", error(failure)),
+        ]),
     }
 }
@@ -0,0 +1,104 @@
+use super::{backup_dir, dry_run, Failure, Outcome, Report};
+use crate::args::{Command, Options};
+use crate::info::Info;
+use crate::output::{output_path, Mode};
+use crate::walk::Input;
+use fest::{backup_file, SaveFolder};
+use std::path::{Path, PathBuf};
+
+// NOTE: Backup folders of save managers are handled as a whole, so a folder is only written once every save in it made it through
+pub fn process_folder(input: &Input, options: &Options) -> Result<Outcome, Failure> {
+    let folder = SaveFolder::read(Path::new(&input.name))?;
+    let saves = folder.saves().count();
+    let others = folder.entries.len() - saves;
+
+    if saves == 0 {
+        return Err(Failure::new(
+            "invalid_data",
+            "Folder has no saves".to_string(),
+        ));
+    }
+
+    let mut report = Report::default();
+
+    let (mode, folder) = match options.command {
+        Command::List => {
+            report.message = format!(
+                "Backup folder with {} saves and {} other files",
+                saves, others
+            );
+
+            for (relative, save) in folder.saves() {
+                let summary = Info::new(save)
+                    .map(|info| info.summary())
+                    .unwrap_or_else(|error| error.to_string());
+
+                report
+                    .message
+                    .push_str(&format!("\n  '{}': {}", relative.display(), summary));
+            }
+
+            return Ok(Outcome::Done(report));
+        }
+        Command::Unpack => (Mode::Decompress, folder.decompress()?),
+        Command::Pack => (Mode::Compress, folder.compress()?),
+        _ => unreachable!("Only folder commands process folders"),
+    };
+
+    let verb = match mode {
+        Mode::Decompress => "decompressed",
+        Mode::Compress => "compressed and verified",
+    };
+
+    if options.dry_run {
+        return match dry_run(input, mode, options, report)? {
+            Outcome::Done(mut report) => {
+                report.message = format!("{}, {} {} saves", report.message, verb, saves);
+                Ok(Outcome::Done(report))
+            }
+            outcome => Ok(outcome),
+        };
+    }
+
+    let output_path =
+        output_path(input, mode, options).map_err(|e| Failure::new("invalid_output", e))?;
+
+    report.backup = match options.force && options.backup && output_path.is_dir() {
+        true => Some(backup_folder(&output_path, options)?),
+        false => None,
+    };
+
+    folder.write_to(&output_path, options.write_options())?;
+
+    report.message = match &report.backup {
+        Some(backup_path) => format!(
+            "Wrote '{}', backed up the previous one to '{}'",
+            output_path.display(),
+            backup_path.display()
+        ),
+        None => format!("Wrote '{}'", output_path.display()),
+    };
+    report.message = format!("{}, {} {} saves", report.message, verb, saves);
+    report.output = Some(output_path.to_string_lossy().to_string());
+
+    Ok(Outcome::Done(report))
+}
+
+// NOTE: Every file of the folder being replaced is backed up the same way single files are, under a directory named after the folder
+fn backup_folder(folder_path: &Path, options: &Options) -> Result<PathBuf, Failure> {
+    let name = folder_path.file_name().unwrap_or_default();
+    let backup_root = backup_dir(folder_path, options).join(name);
+    let keep = Some(options.keep).filter(|&keep| keep > 0);
+
+    let old_folder = SaveFolder::read(folder_path)?;
+    for entry in &old_folder.entries {
+        let backup_dir = match entry.relative.parent() {
+            Some(parent) => backup_root.join(parent),
+            None => backup_root.clone(),
+        };
+
+        backup_file(&folder_path.join(&entry.relative), &backup_dir, keep)?;
+    }
+
+    Ok(backup_root)
+}
@@ -0,0 +1,370 @@
+mod folder;
+mod zipped;
+
+use crate::archive;
+use crate::args::{Command, Options, DEFAULT_BACKUP_DIR, STDIO};
+use crate::info::Info;
+use crate::output::{output_path, Mode};
+use crate::walk::Input;
+use fest::{backup_file, write_atomic, CompHeader, FESData, FESTError, GameVariant, SaveKind};
+use log::{debug, info, warn};
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+pub fn read_file(file_name: &str) -> Result<Vec<u8>, Failure> {
+    let mut raw = vec![];
+
+    match file_name {
+        STDIO => io::stdin().lock().read_to_end(&mut raw),
+        _ => File::open(file_name).and_then(|mut file| file.read_to_end(&mut raw)),
+    }
+    .map_err(|e| Failure::new("read_error", e.to_string()))?;
+
+    Ok(raw)
+}
+
+pub fn process_data(raw: &[u8], file_name: &str, options: &Options) -> Result<FESData, FESTError> {
+    let mut file_data = match (options.scan, options.kind) {
+        (true, _) => FESData::scan_data(raw),
+        (false, Some(kind)) => FESData::process_data_as(raw, kind),
+        (false, None) => FESData::process_file_data(raw, file_name),
+    }?;
+
+    if let Some(kind) = options.kind {
+        file_data.set_kind(kind);
+    }
+
+    if let Some(variant) = options.variant {
+        file_data.set_variant(variant);
+    }
+
+    if let Some(comp_version) = options.comp_version {
+        file_data.set_comp_version(comp_version);
+    }
+
+    Ok(file_data)
+}
+
+#[derive(Debug)]
+pub struct Failure {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl Failure {
+    pub fn new(code: &'static str, message: String) -> Self {
+        Failure { code, message }
+    }
+}
+
+impl From<FESTError> for Failure {
+    fn from(error: FESTError) -> Self {
+        Failure::new(error.code(), error.to_string())
+    }
+}
+
+// NOTE: Everything that could be found out about a file, sizes are of the whole file including the header before the data
+#[derive(Debug, Default)]
+pub struct Report {
+    pub kind: Option<SaveKind>,
+    pub variant: Option<GameVariant>,
+    pub compressed_size: Option<usize>,
+    pub plain_size: Option<usize>,
+    pub checksum_valid: Option<bool>,
+    pub output: Option<String>,
+    pub backup: Option<PathBuf>,
+    pub info: Option<Info>,
+    pub message: String,
+}
+
+impl Report {
+    pub fn new(file_data: &FESData) -> Self {
+        let (compressed_size, plain_size) = match file_data.is_compressed {
+            true => (
+                Some(file_data.raw.len()),
+                file_data
+                    .comp_header()
+                    .map(|comp_header| file_data.header_len() + comp_header.length as usize),
+            ),
+            false => (None, Some(file_data.raw.len())),
+        };
+
+        Report {
+            kind: Some(file_data.kind()),
+            variant: file_data.variant(),
+            compressed_size,
+            plain_size,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Outcome {
+    Done(Report),
+    Skipped(Failure),
+}
+
+pub fn process_file(input: &Input, options: &Options) -> Result<Outcome, Failure> {
+    info!("Processing '{}'", input.name);
+    let start = Instant::now();
+
+    let outcome = process_input(input, options);
+
+    debug!("Finished '{}' in {:?}", input.name, start.elapsed());
+
+    outcome
+}
+
+fn process_input(input: &Input, options: &Options) -> Result<Outcome, Failure> {
+    if options.takes_folders() {
+        return folder::process_folder(input, options);
+    }
+
+    if options.command == Command::ArchiveRestore {
+        return archive::restore(input, options);
+    }
+
+    let raw = read_file(&input.name)?;
+    debug!("Read {} bytes from '{}'", raw.len(), input.name);
+
+    if input.is_zip() {
+        return zipped::process_zip(input, &raw, options);
+    }
+
+    // NOTE: Processing only fails when the magic isn't found, and anything can be inside a walked directory, so those files are skipped instead of failing
+    let file_data = match process_data(&raw, &input.name, options) {
+        Err(error) if input.is_walked() => return Ok(Outcome::Skipped(error.into())),
+        file_data => file_data?,
+    };
+
+    debug!(
+        "'{}' is a {} save with a header of {:#X} bytes",
+        input.name,
+        file_data.kind(),
+        file_data.header_len()
+    );
+
+    let mut report = Report::new(&file_data);
+
+    let mode = match (options.command, file_data.is_compressed) {
+        (Command::Decompress | Command::Auto, true) => Mode::Decompress,
+        (Command::Compress | Command::Auto, false) => Mode::Compress,
+        (Command::Decompress, false) => {
+            return Err(Failure::new(
+                "already_decompressed",
+                "File is already decompressed".to_string(),
+            ))
+        }
+        (Command::Compress, true) => {
+            return Err(Failure::new(
+                "already_compressed",
+                "File is already compressed".to_string(),
+            ))
+        }
+        (Command::Info, _) => {
+            let info = Info::new(&file_data)?;
+
+            report.plain_size = Some(info.plain_size);
+            report.checksum_valid = info.checksum_valid;
+            report.message = info.summary();
+            report.info = Some(info);
+            return Ok(Outcome::Done(report));
+        }
+        (Command::Verify, _) => {
+            report.message = verify(&file_data)?;
+            report.checksum_valid = Some(true).filter(|_| file_data.is_compressed);
+            return Ok(Outcome::Done(report));
+        }
+        (Command::ArchiveAdd, _) => return archive::add(input, &file_data, options),
+        (
+            Command::Watch
+            | Command::Locate
+            | Command::List
+            | Command::Unpack
+            | Command::Pack
+            | Command::Textconv
+            | Command::Diff
+            | Command::Hexdump
+            | Command::ArchiveList
+            | Command::ArchiveRestore
+            | Command::Help,
+            _,
+        ) => {
+            unreachable!("Only commands that process files get here")
+        }
+    };
+
+    let file_data = match mode {
+        Mode::Decompress => file_data.decompress()?,
+        Mode::Compress => keep_comp_version(file_data, input, options).compress()?,
+    };
+
+    match mode {
+        Mode::Decompress => {
+            report.plain_size = Some(file_data.raw.len());
+            report.checksum_valid = file_data
+                .comp_header()
+                .map(|comp_header| file_data.checksum().map(|c| c == comp_header.checksum))
+                .transpose()?;
+        }
+        Mode::Compress => report.compressed_size = Some(file_data.raw.len()),
+    }
+
+    if options.dry_run {
+        verify(&file_data)?;
+    }
+
+    deliver(&file_data.raw, input, mode, options, report)
+}
+
+// NOTE: Decompressed files don't carry their COMP version, so the one of the save they are about to overwrite is kept, and falling back to the default is warned about
+fn keep_comp_version(mut file_data: FESData, input: &Input, options: &Options) -> FESData {
+    if file_data.comp_header().is_some() {
+        return file_data;
+    }
+
+    let overwritten = match options.writes_stdout() {
+        true => None,
+        false => output_path(input, Mode::Compress, options)
+            .ok()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|raw| FESData::process_data(&raw).ok())
+            .and_then(|overwritten| overwritten.comp_header()),
+    };
+
+    match overwritten {
+        Some(comp_header) => file_data.set_comp_version(comp_header.version),
+        None => warn!(
+            "'{}' has no COMP version to keep, compressing it with version {} (Give another with '--comp-version')",
+            input.name,
+            CompHeader::DEFAULT_VERSION
+        ),
+    }
+
+    file_data
+}
+
+// NOTE: Everything that happens to a result once it is ready, wherever it has to go
+fn deliver(
+    raw: &[u8],
+    input: &Input,
+    mode: Mode,
+    options: &Options,
+    mut report: Report,
+) -> Result<Outcome, Failure> {
+    if options.dry_run {
+        return dry_run(input, mode, options, report);
+    }
+
+    if options.writes_stdout() {
+        let mut stdout = io::stdout().lock();
+        stdout
+            .write_all(raw)
+            .and_then(|_| stdout.flush())
+            .map_err(|e| Failure::new("write_error", e.to_string()))?;
+
+        report.output = Some(STDIO.to_string());
+        report.message = "Wrote to stdout".to_string();
+        return Ok(Outcome::Done(report));
+    }
+
+    let output_path =
+        output_path(input, mode, options).map_err(|e| Failure::new("invalid_output", e))?;
+    if let Some(parent) = output_path.parent().filter(|parent| !parent.exists()) {
+        fs::create_dir_all(parent).map_err(|e| Failure::new("write_error", e.to_string()))?;
+    }
+
+    report.backup = match options.force && options.backup {
+        true => backup(&output_path, options)?,
+        false => None,
+    };
+
+    write_atomic(&output_path, raw, options.write_options())?;
+
+    report.message = match &report.backup {
+        Some(backup_path) => format!(
+            "Wrote '{}', backed up the previous one to '{}'",
+            output_path.display(),
+            backup_path.display()
+        ),
+        None => format!("Wrote '{}'", output_path.display()),
+    };
+    report.output = Some(output_path.to_string_lossy().to_string());
+
+    Ok(Outcome::Done(report))
+}
+
+// NOTE: Goes through the same checks a real run would, but nothing is written or created
+fn dry_run(
+    input: &Input,
+    mode: Mode,
+    options: &Options,
+    mut report: Report,
+) -> Result<Outcome, Failure> {
+    if options.writes_stdout() {
+        report.output = Some(STDIO.to_string());
+        report.message = "Would write to stdout".to_string();
+        return Ok(Outcome::Done(report));
+    }
+
+    let output_path =
+        output_path(input, mode, options).map_err(|e| Failure::new("invalid_output", e))?;
+
+    report.message = match (output_path.exists(), options.force, options.backup) {
+        (false, _, _) => format!("Would write '{}'", output_path.display()),
+        (true, false, _) => {
+            return Err(Failure::new(
+                "file_exists",
+                format!(
+                    "Would not overwrite '{}', it already exists",
+                    output_path.display()
+                ),
+            ))
+        }
+        (true, true, false) => format!("Would overwrite '{}'", output_path.display()),
+        (true, true, true) => format!(
+            "Would overwrite '{}', backing up the previous one into '{}'",
+            output_path.display(),
+            backup_dir(&output_path, options).display()
+        ),
+    };
+    report.output = Some(output_path.to_string_lossy().to_string());
+
+    Ok(Outcome::Done(report))
+}
+
+fn backup_dir(output_path: &Path, options: &Options) -> PathBuf {
+    match &options.backup_dir {
+        Some(backup_dir) => PathBuf::from(backup_dir),
+        None => output_path
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(DEFAULT_BACKUP_DIR),
+    }
+}
+
+pub fn backup(output_path: &Path, options: &Options) -> Result<Option<PathBuf>, Failure> {
+    let backup_dir = backup_dir(output_path, options);
+    let keep = Some(options.keep).filter(|&keep| keep > 0);
+
+    Ok(backup_file(output_path, &backup_dir, keep)?)
+}
+
+fn verify(file_data: &FESData) -> Result<String, Failure> {
+    match file_data.verify()? {
+        true => Ok("File is valid".to_string()),
+        false if file_data.is_compressed => Err(Failure::new(
+            "checksum_mismatch",
+            "Checksum doesn't match the data".to_string(),
+        )),
+        false => Err(Failure::new(
+            "round_trip_mismatch",
+            "Data doesn't survive a compression round trip".to_string(),
+        )),
+    }
+}
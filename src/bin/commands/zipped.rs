@@ -0,0 +1,135 @@
+use super::{deliver, process_data, verify, Failure, Outcome, Report};
+use crate::args::{Command, Options};
+use crate::info::Info;
+use crate::output::Mode;
+use crate::walk::Input;
+use fest::{read_zip, rewrite_zip, FESData};
+use std::io::Cursor;
+
+// NOTE: Archives are handled as a whole, the first save inside decides the direction for "auto" and saves already going the other way are left as they are
+pub fn process_zip(input: &Input, raw: &[u8], options: &Options) -> Result<Outcome, Failure> {
+    // NOTE: Entries are probed like any other file, so "--scan", "--kind" and "--game" apply inside archives too
+    let probe = |raw: &[u8], name: &str| process_data(raw, name, options);
+
+    let saves = match read_zip(Cursor::new(raw), probe) {
+        Ok(saves) if saves.is_empty() => Err(Failure::new(
+            "invalid_data",
+            "Archive has no saves".to_string(),
+        )),
+        Ok(saves) => Ok(saves),
+        Err(error) => Err(error.into()),
+    };
+
+    let saves = match saves {
+        Err(failure) if input.is_walked() => return Ok(Outcome::Skipped(failure)),
+        saves => saves?,
+    };
+
+    let mut report = Report::default();
+
+    let mode = match (options.command, saves[0].data.is_compressed) {
+        (Command::Decompress, _) | (Command::Auto, true) => Mode::Decompress,
+        (Command::Compress, _) | (Command::Auto, false) => Mode::Compress,
+        (Command::Info, _) => {
+            let summaries = saves
+                .iter()
+                .map(|save| Info::new(&save.data).map(|info| (&save.name, info.summary())))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            report.message = format!("Archive with {} saves", saves.len());
+            for (name, summary) in summaries {
+                report
+                    .message
+                    .push_str(&format!("\n  '{}': {}", name, summary));
+            }
+
+            return Ok(Outcome::Done(report));
+        }
+        (Command::Verify, _) => {
+            for save in &saves {
+                in_entry(&save.name, verify(&save.data))?;
+            }
+
+            report.message = format!("All {} saves in the archive are valid", saves.len());
+            return Ok(Outcome::Done(report));
+        }
+        // NOTE: Restoring writes back where a save was added from, which a save inside an archive doesn't have
+        (Command::ArchiveAdd, _) => {
+            let failure = Failure::new(
+                "unsupported",
+                "Saves inside ZIP archives can't be added, extract them first".to_string(),
+            );
+
+            return match input.is_walked() {
+                true => Ok(Outcome::Skipped(failure)),
+                false => Err(failure),
+            };
+        }
+        (
+            Command::Watch
+            | Command::Locate
+            | Command::List
+            | Command::Unpack
+            | Command::Pack
+            | Command::Textconv
+            | Command::Diff
+            | Command::Hexdump
+            | Command::ArchiveList
+            | Command::ArchiveRestore
+            | Command::Help,
+            _,
+        ) => {
+            unreachable!("Only commands that process files get here")
+        }
+    };
+
+    let mut converted: Vec<(String, FESData)> = vec![];
+    let rewritten = rewrite_zip(Cursor::new(raw), Cursor::new(vec![]), probe, |save| {
+        let data = match (mode, save.data.is_compressed) {
+            (Mode::Decompress, true) => save.data.clone().decompress()?,
+            (Mode::Compress, false) => save.data.clone().compress()?,
+            _ => return Ok(None),
+        };
+
+        converted.push((save.name.to_string(), data.clone()));
+
+        Ok(Some(data))
+    })?
+    .into_inner();
+
+    if converted.is_empty() {
+        return Err(match mode {
+            Mode::Decompress => Failure::new(
+                "already_decompressed",
+                "Every save in the archive is already decompressed".to_string(),
+            ),
+            Mode::Compress => Failure::new(
+                "already_compressed",
+                "Every save in the archive is already compressed".to_string(),
+            ),
+        });
+    }
+
+    if options.dry_run {
+        for (name, data) in &converted {
+            in_entry(name, verify(data))?;
+        }
+    }
+
+    let verb = match mode {
+        Mode::Decompress => "decompressed",
+        Mode::Compress => "compressed",
+    };
+
+    match deliver(&rewritten, input, mode, options, report)? {
+        Outcome::Done(mut report) => {
+            report.message = format!("{}, {} {} saves", report.message, verb, converted.len());
+            Ok(Outcome::Done(report))
+        }
+        outcome => Ok(outcome),
+    }
+}
+
+fn in_entry<T>(name: &str, result: Result<T, Failure>) -> Result<T, Failure> {
+    result.map_err(|failure| Failure::new(failure.code, format!("'{}': {}", name, failure.message)))
+}
@@ -0,0 +1,161 @@
+use crate::args::{Format, Options};
+use crate::commands::{process_data, read_file};
+use crate::json::Value;
+use fest::{diff_saves, FESData, RegionDiff, SectionKind};
+
+const SHOWN_BYTES: usize = 16;
+
+// NOTE: Returns how many regions changed, so having none can be told apart
+pub fn diff(options: &Options) -> Result<usize, String> {
+    let [old, new] = &options.files[..] else {
+        return Err("Command 'diff' needs two files".to_string());
+    };
+
+    let diffs = diff_saves(&read_save(old, options)?, &read_save(new, options)?)
+        .map_err(|e| e.to_string())?;
+
+    for region_diff in &diffs {
+        match options.format {
+            Format::Text => println!("{}", describe(region_diff)),
+            Format::Json => println!("{}", record(region_diff)),
+        }
+    }
+
+    Ok(diffs.len())
+}
+
+fn read_save(file_name: &str, options: &Options) -> Result<FESData, String> {
+    let raw = read_file(file_name).map_err(|failure| {
+        format!(
+            "Error ocurred when reading '{}': '{}'",
+            file_name, failure.message
+        )
+    })?;
+
+    process_data(&raw, file_name, options)
+        .map_err(|e| format!("Error ocurred when reading '{}': '{}'", file_name, e))
+}
+
+fn describe(region_diff: &RegionDiff) -> String {
+    let title = region_diff.region.name();
+
+    let (old_offset, new_offset, old_len, new_len) = match (
+        region_diff.old_offset,
+        region_diff.new_offset,
+        region_diff.old_len,
+        region_diff.new_len,
+    ) {
+        (Some(old_offset), Some(new_offset), Some(old_len), Some(new_len)) => {
+            (old_offset, new_offset, old_len, new_len)
+        }
+        (Some(offset), None, Some(len), _) => {
+            return format!(
+                "{} at {:#X}, {} bytes: Only in the first save",
+                title, offset, len
+            )
+        }
+        (None, Some(offset), _, Some(len)) => {
+            return format!(
+                "{} at {:#X}, {} bytes: Only in the second save",
+                title, offset, len
+            )
+        }
+        _ => return format!("{}: Missing from both saves", title),
+    };
+
+    let location = match old_offset == new_offset {
+        true => format!("at {:#X}", old_offset),
+        false => format!("at {:#X} -> {:#X}", old_offset, new_offset),
+    };
+    let size = match old_len == new_len {
+        true => format!("{} bytes", old_len),
+        false => format!("{} -> {} bytes", old_len, new_len),
+    };
+
+    let mut text = format!(
+        "{} {}, {}: {} changed ranges",
+        title,
+        location,
+        size,
+        region_diff.changes.len()
+    );
+
+    for change in &region_diff.changes {
+        let length = change.old.len().max(change.new.len());
+
+        text.push_str(&format!(
+            "\n  +{:#06X} ({:#X}), {} bytes: {} -> {}",
+            change.offset,
+            old_offset + change.offset,
+            length,
+            hex(&change.old),
+            hex(&change.new)
+        ));
+    }
+
+    text
+}
+
+// NOTE: Long runs are cut short, the JSON output has all of them
+fn hex(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return "(None)".to_string();
+    }
+
+    let shown = bytes
+        .iter()
+        .take(SHOWN_BYTES)
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    match bytes.len() > SHOWN_BYTES {
+        true => format!("{} ...", shown),
+        false => shown,
+    }
+}
+
+fn record(region_diff: &RegionDiff) -> Value {
+    let (region, id) = match region_diff.region {
+        SectionKind::ChapterHeader => ("chapter_header", None),
+        SectionKind::CompHeader => ("comp_header", None),
+        SectionKind::HuffmanHeader => ("huffman_header", None),
+        SectionKind::CodeTree => ("code_tree", None),
+        SectionKind::CompressedData => ("compressed_data", None),
+        SectionKind::IndexHeader => ("index_header", None),
+        SectionKind::IndexEntry(id) => ("index_entry", Some(id)),
+        SectionKind::IndexGap => ("index_gap", None),
+        SectionKind::Block(id) => ("block", Some(id)),
+        SectionKind::Padding(id) => ("padding", Some(id)),
+    };
+    let id = id.map(|id| format!("{:#010X}", id));
+    let changes = region_diff
+        .changes
+        .iter()
+        .map(|change| {
+            let bytes = |bytes: &[u8]| {
+                bytes
+                    .iter()
+                    .map(|byte| format!("{:02X}", byte))
+                    .collect::<String>()
+            };
+
+            Value::Object(vec![
+                ("offset", change.offset.into()),
+                ("old", bytes(&change.old).into()),
+                ("new", bytes(&change.new).into()),
+            ])
+        })
+        .collect();
+
+    Value::Object(vec![
+        ("region", region.into()),
+        ("id", id.into()),
+        ("name", region_diff.name.clone().into()),
+        ("old_offset", region_diff.old_offset.into()),
+        ("new_offset", region_diff.new_offset.into()),
+        ("old_length", region_diff.old_len.into()),
+        ("new_length", region_diff.new_len.into()),
+        ("changes", Value::Array(changes)),
+    ])
+}
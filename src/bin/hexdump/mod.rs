@@ -0,0 +1,171 @@
+use crate::args::{Color, Options};
+use crate::commands::{process_data, read_file};
+use fest::{FESData, FESTError, Field, FieldValue, Section, SectionKind};
+use std::{
+    env,
+    io::{self, IsTerminal, Write},
+};
+
+const BYTES_PER_LINE: usize = 16;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const CYAN: &str = "\x1b[36m";
+
+// NOTE: Relative offsets start at each section, so a section moving doesn't change every line of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Offsets {
+    Absolute,
+    Relative,
+}
+
+// NOTE: Compressed files only get their headers dumped as they are, and then the data they decompress into, which is what is worth reading
+pub fn hexdump(options: &Options) -> Result<(), String> {
+    let file_name = options.files.first().ok_or("No file given".to_string())?;
+    let raw = read_file(file_name).map_err(|failure| failure.message)?;
+    let file_data = process_data(&raw, file_name, options).map_err(|e| e.to_string())?;
+
+    let color = match options.color {
+        Color::Always => true,
+        Color::Never => false,
+        Color::Auto => io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none(),
+    };
+
+    let text = dump(&file_data, color).map_err(|e| e.to_string())?;
+
+    let mut stdout = io::stdout().lock();
+    stdout
+        .write_all(text.as_bytes())
+        .and_then(|_| stdout.flush())
+        .map_err(|e| e.to_string())
+}
+
+fn dump(file_data: &FESData, color: bool) -> Result<String, FESTError> {
+    let mut text = String::new();
+
+    if file_data.is_compressed {
+        for section in file_data.layout()? {
+            match section.kind {
+                SectionKind::CompHeader | SectionKind::HuffmanHeader => text.push_str(
+                    &dump_section(&file_data.raw, &section, color, Offsets::Absolute),
+                ),
+                SectionKind::ChapterHeader => {}
+                _ => text.push_str(&format!("{}\n", title(&section, color))),
+            }
+        }
+
+        text.push_str(&format!("\n{}\n", paint("Decompressed:", BOLD, color)));
+    }
+
+    let decompressed = file_data.clone().decompress()?;
+    for section in decompressed.layout()? {
+        text.push_str(&dump_section(
+            &decompressed.raw,
+            &section,
+            color,
+            Offsets::Absolute,
+        ));
+    }
+
+    Ok(text)
+}
+
+pub fn title(section: &Section, color: bool) -> String {
+    let style = match section.kind {
+        SectionKind::Block(_) => CYAN,
+        SectionKind::IndexGap
+        | SectionKind::Padding(_)
+        | SectionKind::CodeTree
+        | SectionKind::CompressedData => DIM,
+        _ => YELLOW,
+    };
+
+    paint(
+        &format!(
+            "{}, {:#X} bytes at {:#010X}",
+            section.kind.name(),
+            section.len,
+            section.offset
+        ),
+        &format!("{}{}", BOLD, style),
+        color,
+    )
+}
+
+// NOTE: Lines start at the start of each section instead of at multiples of 16, so every section begins on a line of its own
+pub fn dump_section(data: &[u8], section: &Section, color: bool, offsets: Offsets) -> String {
+    let end = (section.offset + section.len).min(data.len());
+    let bytes = data.get(section.offset..end).unwrap_or_default();
+
+    format!(
+        "{}\n{}",
+        title(section, color),
+        hex_lines(bytes, section.offset, &section.fields, color, offsets)
+    )
+}
+
+// NOTE: "start" is where "bytes" are in the whole data, which is what field offsets are relative to
+pub fn hex_lines(
+    bytes: &[u8],
+    start: usize,
+    fields: &[FieldValue],
+    color: bool,
+    offsets: Offsets,
+) -> String {
+    let mut text = String::new();
+
+    for (line, chunk) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+        let line_start = start + line * BYTES_PER_LINE;
+        let in_field = |offset: usize| {
+            fields
+                .iter()
+                .any(|field| (field.offset..field.offset + Field::LEN).contains(&offset))
+        };
+
+        let hex = (0..BYTES_PER_LINE)
+            .map(|i| match chunk.get(i) {
+                Some(byte) if in_field(line_start + i) => {
+                    paint(&format!("{:02X}", byte), GREEN, color)
+                }
+                Some(byte) => format!("{:02X}", byte),
+                None => "  ".to_string(),
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        let ascii = chunk
+            .iter()
+            .map(|&byte| match byte.is_ascii_graphic() || byte == b' ' {
+                true => byte as char,
+                false => '.',
+            })
+            .collect::<String>();
+        let annotations = fields
+            .iter()
+            .filter(|field| (line_start..line_start + chunk.len()).contains(&field.offset))
+            .map(|field| format!("{}={:#X}", field.field.name, field.value))
+            .collect::<Vec<String>>()
+            .join(" ");
+        let label = match offsets {
+            Offsets::Absolute => format!("{:08X}", line_start),
+            Offsets::Relative => format!("+{:06X}", line * BYTES_PER_LINE),
+        };
+
+        text.push_str(&format!("  {}  {}  |{:<16}|", label, hex, ascii));
+        if !annotations.is_empty() {
+            text.push_str(&format!("  {}", paint(&annotations, GREEN, color)));
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+fn paint(text: &str, style: &str, color: bool) -> String {
+    match color {
+        true => format!("{}{}{}", style, text, RESET),
+        false => text.to_string(),
+    }
+}
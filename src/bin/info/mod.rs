@@ -0,0 +1,205 @@
+use crate::json::Value;
+use fest::{CompHeader, FESData, FESTError, GameVariant, SaveKind, TreeInfo};
+use std::fmt;
+
+#[derive(Debug)]
+pub struct BlockInfo {
+    pub id: u32,
+    pub name: Option<String>,
+    pub offset: u32,
+    pub length: usize,
+}
+
+#[derive(Debug)]
+pub struct Info {
+    pub variant: Option<GameVariant>,
+    pub kind: SaveKind,
+    pub header_len: usize,
+    pub is_compressed: bool,
+    pub comp_header: Option<CompHeader>,
+    pub compressed_size: Option<usize>,
+    pub plain_size: usize,
+    pub tree: Option<TreeInfo>,
+    pub checksum: u32,
+    pub checksum_valid: Option<bool>, // NOTE: Only known when there's a COMP header to compare against
+    pub blocks: Vec<BlockInfo>,
+}
+
+impl Info {
+    // NOTE: Compressed files get decompressed to look inside them, nothing is written
+    pub fn new(file_data: &FESData) -> Result<Self, FESTError> {
+        let decompressed = file_data.clone().decompress()?;
+        let comp_header = file_data.comp_header();
+        let checksum = decompressed.checksum()?;
+
+        let blocks = decompressed
+            .index()?
+            .blocks
+            .iter()
+            .map(|block| BlockInfo {
+                id: block.id,
+                name: block.name(),
+                offset: block.offset,
+                length: block.data.len(),
+            })
+            .collect();
+
+        Ok(Info {
+            variant: file_data.variant(),
+            kind: file_data.kind(),
+            header_len: file_data.header_len(),
+            is_compressed: file_data.is_compressed,
+            comp_header,
+            compressed_size: Some(file_data.raw.len()).filter(|_| file_data.is_compressed),
+            plain_size: decompressed.raw.len(),
+            tree: file_data.tree_info()?,
+            checksum,
+            checksum_valid: comp_header.map(|comp_header| comp_header.checksum == checksum),
+            blocks,
+        })
+    }
+
+    pub fn summary(&self) -> String {
+        let state = match self.is_compressed {
+            true => "compressed",
+            false => "decompressed",
+        };
+        let size = self.compressed_size.unwrap_or(self.plain_size);
+
+        format!(
+            "{} {} save, {}, {} bytes with a header of {:#X} bytes",
+            self.game(),
+            self.kind,
+            state,
+            size,
+            self.header_len
+        )
+    }
+
+    fn game(&self) -> String {
+        self.variant
+            .map(|variant| variant.to_string())
+            .unwrap_or("Unknown".to_string())
+    }
+
+    fn ratio(&self) -> Option<f64> {
+        self.compressed_size
+            .filter(|_| self.plain_size > 0)
+            .map(|compressed_size| compressed_size as f64 * 100.0 / self.plain_size as f64)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let comp_header = self.comp_header.map(|comp_header| {
+            Value::Object(vec![
+                ("version", (comp_header.version as usize).into()),
+                ("length", (comp_header.length as usize).into()),
+                ("checksum", format!("{:#010X}", comp_header.checksum).into()),
+            ])
+        });
+        let tree = self.tree.map(|tree| {
+            Value::Object(vec![
+                ("size", tree.size.into()),
+                ("symbols", tree.symbols.into()),
+            ])
+        });
+        let blocks = self
+            .blocks
+            .iter()
+            .map(|block| {
+                Value::Object(vec![
+                    ("id", format!("{:#010X}", block.id).into()),
+                    ("name", block.name.clone().into()),
+                    ("offset", (block.offset as usize).into()),
+                    ("length", block.length.into()),
+                ])
+            })
+            .collect();
+
+        Value::Object(vec![
+            ("header_len", self.header_len.into()),
+            ("compressed", self.is_compressed.into()),
+            ("comp_header", comp_header.unwrap_or(Value::Null)),
+            ("tree", tree.unwrap_or(Value::Null)),
+            ("checksum", format!("{:#010X}", self.checksum).into()),
+            ("blocks", Value::Array(blocks)),
+        ])
+    }
+}
+
+impl fmt::Display for Info {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  Game:            {}", self.game())?;
+        writeln!(f, "  Kind:            {}", self.kind)?;
+
+        match self.header_len {
+            0 => writeln!(f, "  Chapter header:  None")?,
+            header_len => writeln!(f, "  Chapter header:  {:#X} bytes", header_len)?,
+        }
+
+        match self.comp_header {
+            Some(comp_header) => writeln!(
+                f,
+                "  COMP header:     Version {}, length {:#X}, checksum {:#010X}{}",
+                comp_header.version,
+                comp_header.length,
+                comp_header.checksum,
+                match self.is_compressed {
+                    true => "",
+                    false => " (Before decompressing)",
+                }
+            )?,
+            None => writeln!(f, "  COMP header:     None")?,
+        }
+
+        match (self.compressed_size, self.ratio()) {
+            (Some(compressed_size), Some(ratio)) => writeln!(
+                f,
+                "  Size:            {} bytes compressed, {} decompressed ({:.1}%)",
+                compressed_size, self.plain_size, ratio
+            )?,
+            _ => writeln!(
+                f,
+                "  Size:            {} bytes decompressed",
+                self.plain_size
+            )?,
+        }
+
+        match self.tree {
+            Some(tree) => writeln!(
+                f,
+                "  Huffman tree:    {} bytes, {} symbols",
+                tree.size, tree.symbols
+            )?,
+            None => writeln!(f, "  Huffman tree:    None")?,
+        }
+
+        match self.checksum_valid {
+            Some(true) => writeln!(f, "  Checksum:        {:#010X}, valid", self.checksum)?,
+            Some(false) => writeln!(
+                f,
+                "  Checksum:        {:#010X}, doesn't match",
+                self.checksum
+            )?,
+            None => writeln!(f, "  Checksum:        {:#010X}", self.checksum)?,
+        }
+
+        write!(f, "  Blocks:          {}", self.blocks.len())?;
+
+        for block in &self.blocks {
+            write!(
+                f,
+                "\n    {:#010X} {:<6} at {:#06X}, {} bytes",
+                block.id,
+                block
+                    .name
+                    .as_ref()
+                    .map(|name| format!("'{}'", name))
+                    .unwrap_or_default(),
+                block.offset,
+                block.length
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use crate::args::{Format, Options};
+use crate::json::Value;
+use fest::{default_roots, locate_saves, LocatedSave};
+use std::path::PathBuf;
+
+// NOTE: Returns how many saves were found, so not finding any can be told apart
+pub fn locate(options: &Options) -> Result<usize, String> {
+    let roots = match options.files.is_empty() {
+        true => default_roots(),
+        false => options.files.iter().map(PathBuf::from).collect(),
+    };
+
+    if roots.is_empty() {
+        return Err("No emulator data directory found, give one to search".to_string());
+    }
+
+    let saves = locate_saves(&roots).map_err(|e| e.to_string())?;
+
+    for save in &saves {
+        match options.format {
+            Format::Text => println!("{}", line(save)),
+            Format::Json => println!("{}", record(save)),
+        }
+    }
+
+    Ok(saves.len())
+}
+
+fn line(save: &LocatedSave) -> String {
+    let kind = match (save.kind, save.slot) {
+        (Some(kind), Some(slot)) => format!("{} save, slot {}", kind, slot),
+        (Some(kind), None) => format!("{} save", kind),
+        (None, _) => "Not a save".to_string(),
+    };
+
+    format!(
+        "'{}': {} ({}, {:016X}), {}",
+        save.path.display(),
+        save.title.variant,
+        save.title.region,
+        save.title.id,
+        kind
+    )
+}
+
+fn record(save: &LocatedSave) -> Value {
+    Value::Object(vec![
+        ("path", save.path.to_string_lossy().to_string().into()),
+        ("title_id", format!("{:016X}", save.title.id).into()),
+        ("game", save.title.variant.name().to_lowercase().into()),
+        ("region", save.title.region.into()),
+        (
+            "kind",
+            save.kind.map(|kind| kind.name().to_lowercase()).into(),
+        ),
+        ("slot", save.slot.map(|slot| slot as usize).into()),
+    ])
+}
@@ -0,0 +1,112 @@
+use crate::args::Options;
+use crate::walk::{Input, ZIP_EXTENSION};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Decompress,
+    Compress,
+}
+
+impl Mode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Decompress => "dec",
+            Mode::Compress => "com",
+        }
+    }
+}
+
+const PLACEHOLDERS: [&str; 4] = ["name", "stem", "ext", "mode"];
+
+pub fn check_template(template: &str) -> Result<(), String> {
+    render(template, &PLACEHOLDERS.map(|placeholder| (placeholder, ""))).map(|_| ())
+}
+
+fn render(template: &str, values: &[(&str, &str)]) -> Result<String, String> {
+    let mut rendered = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let end = start
+            + rest[start..]
+                .find('}')
+                .ok_or(format!("Unclosed placeholder in '{}'", template))?;
+        let placeholder = &rest[(start + 1)..end];
+        let (_, value) = values
+            .iter()
+            .find(|(name, _)| *name == placeholder)
+            .ok_or(format!(
+                "Unknown placeholder '{{{}}}' in '{}'",
+                placeholder, template
+            ))?;
+
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(value);
+        rest = &rest[(end + 1)..];
+    }
+
+    rendered.push_str(rest);
+
+    Ok(rendered)
+}
+
+pub fn output_path(input: &Input, mode: Mode, options: &Options) -> Result<PathBuf, String> {
+    if let Some(output) = &options.output {
+        return Ok(PathBuf::from(output));
+    }
+
+    let path = Path::new(&input.name);
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or(input.name.to_string());
+
+    let output_name = match &options.name_template {
+        Some(template) => {
+            let stem = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let ext = path
+                .extension()
+                .map(|ext| format!(".{}", ext.to_string_lossy()))
+                .unwrap_or_default();
+
+            render(
+                template,
+                &[
+                    ("name", &name),
+                    ("stem", &stem),
+                    ("ext", &ext),
+                    ("mode", mode.name()),
+                ],
+            )?
+        }
+        // NOTE: Without a template, decompressed files get "_dec" appended, and compressing one of those gives back the original name. Archives keep their extension at the end
+        None => {
+            let (base, ext) = match input.is_zip() {
+                true => name.split_at(name.len() - ZIP_EXTENSION.len()),
+                false => (name.as_str(), ""),
+            };
+
+            match (mode, base.strip_suffix("_dec")) {
+                (Mode::Decompress, _) => format!("{}_dec{}", base, ext),
+                (Mode::Compress, Some(original_name)) => format!("{}{}", original_name, ext),
+                (Mode::Compress, None) => format!("{}_com{}", base, ext),
+            }
+        }
+    };
+
+    Ok(match &options.out_dir {
+        Some(out_dir) => match input
+            .relative
+            .as_ref()
+            .and_then(|relative| relative.parent())
+        {
+            Some(relative_dir) => Path::new(out_dir).join(relative_dir).join(output_name),
+            None => Path::new(out_dir).join(output_name),
+        },
+        None => path.with_file_name(output_name),
+    })
+}
@@ -0,0 +1,62 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+// NOTE: Items are worked on by "jobs" threads at once, but "report" always gets them in their original order, as soon as every item before them is done
+pub fn run_ordered<T, R, W, F>(items: &[T], jobs: usize, work: W, mut report: F)
+where
+    T: Sync,
+    R: Send,
+    W: Fn(&T) -> R + Sync,
+    F: FnMut(&T, R),
+{
+    let jobs = jobs.clamp(1, items.len().max(1));
+
+    if jobs == 1 {
+        for item in items {
+            report(item, work(item));
+        }
+
+        return;
+    }
+
+    let next_item = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel::<(usize, R)>();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let (next_item, work) = (&next_item, &work);
+
+            scope.spawn(move || loop {
+                let index = next_item.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+
+                if sender.send((index, work(item))).is_err() {
+                    break;
+                }
+            });
+        }
+
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        let mut next_report = 0;
+
+        for (index, result) in receiver {
+            pending.insert(index, result);
+
+            while let Some(result) = pending.remove(&next_report) {
+                report(&items[next_report], result);
+                next_report += 1;
+            }
+        }
+    });
+}
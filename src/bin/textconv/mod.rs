@@ -0,0 +1,52 @@
+use crate::args::Options;
+use crate::commands::{process_data, read_file};
+use crate::hexdump::{dump_section, hex_lines, Offsets};
+use crate::info::Info;
+use fest::{FESData, FESTError};
+use std::io::{self, Write};
+
+// NOTE: Meant for git's "textconv", so the same save always gives the same text, and anything that can't be read as a save is still dumped instead of failing the diff
+pub fn textconv(options: &Options) -> Result<(), String> {
+    let file_name = options.files.first().ok_or("No file given".to_string())?;
+    let raw = read_file(file_name).map_err(|failure| failure.message)?;
+
+    let text = match process_data(&raw, file_name, options) {
+        Ok(file_data) => dump(&file_data).unwrap_or_else(|error| {
+            format!("Save that couldn't be read: {}\n{}", error, raw_data(&raw))
+        }),
+        Err(_) => format!("Not a save\n{}", raw_data(&raw)),
+    };
+
+    let mut stdout = io::stdout().lock();
+    stdout
+        .write_all(text.as_bytes())
+        .and_then(|_| stdout.flush())
+        .map_err(|e| e.to_string())
+}
+
+fn dump(file_data: &FESData) -> Result<String, FESTError> {
+    let info = Info::new(file_data)?;
+    let decompressed = file_data.clone().decompress()?;
+
+    let mut text = format!("{}\n", info);
+
+    for section in decompressed.layout()? {
+        text.push('\n');
+        text.push_str(&dump_section(
+            &decompressed.raw,
+            &section,
+            false,
+            Offsets::Relative,
+        ));
+    }
+
+    Ok(text)
+}
+
+fn raw_data(raw: &[u8]) -> String {
+    format!(
+        "\nRaw data, {:#X} bytes\n{}",
+        raw.len(),
+        hex_lines(raw, 0, &[], false, Offsets::Relative)
+    )
+}
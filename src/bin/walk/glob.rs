@@ -0,0 +1,115 @@
+// NOTE: Only what's needed for filtering save folders: "*" and "?" inside a single path component, and "**" for any number of components. Patterns without "/" only look at the file name
+pub fn matches(pattern: &str, relative_path: &str) -> bool {
+    match pattern.contains('/') {
+        true => {
+            let pattern = pattern.split('/').collect::<Vec<&str>>();
+            let path = relative_path.split('/').collect::<Vec<&str>>();
+
+            matches_components(&pattern, &path)
+        }
+        false => {
+            let name = relative_path.rsplit('/').next().unwrap_or(relative_path);
+
+            matches_component(&chars(pattern), &chars(name))
+        }
+    }
+}
+
+fn matches_components(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => {
+            matches_components(&pattern[1..], path)
+                || (!path.is_empty() && matches_components(pattern, &path[1..]))
+        }
+        (Some(component), Some(name)) => {
+            matches_component(&chars(component), &chars(name))
+                && matches_components(&pattern[1..], &path[1..])
+        }
+        _ => false,
+    }
+}
+
+// NOTE: Compared by character, so "?" stands for one character of a name and not one byte of it
+fn chars(text: &str) -> Vec<char> {
+    text.chars().collect()
+}
+
+fn matches_component(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            matches_component(&pattern[1..], name)
+                || (!name.is_empty() && matches_component(pattern, &name[1..]))
+        }
+        (Some('?'), Some(_)) => matches_component(&pattern[1..], &name[1..]),
+        (Some(expected), Some(found)) => {
+            expected == found && matches_component(&pattern[1..], &name[1..])
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_name_patterns() {
+        let cases = [
+            ("Chapter*", "Chapter1", true),
+            ("Chapter*", "saves/Chapter1_dec", true),
+            ("Chapter*", "Chapter", true),
+            ("Chapter*", "Global", false),
+            ("Chapter?", "Chapter1", true),
+            ("Chapter?", "Chapter12", false),
+            ("Chapter?", "Chapter", false),
+            ("*_dec", "saves/Chapter1_dec", true),
+            ("*_dec", "Chapter1_dec/Global", false),
+            ("Global", "saves/Global", true),
+            ("Global", "Global_dec", false),
+            ("*", "", true),
+            ("?", "", false),
+        ];
+
+        for (pattern, path, expected) in cases {
+            assert_eq!(matches(pattern, path), expected, "{} {}", pattern, path);
+        }
+    }
+
+    #[test]
+    fn path_patterns() {
+        let cases = [
+            ("saves/Chapter*", "saves/Chapter1", true),
+            ("saves/Chapter*", "Chapter1", false),
+            ("saves/Chapter*", "old/saves/Chapter1", false),
+            ("*/Global", "saves/Global", true),
+            ("*/Global", "a/b/Global", false),
+            ("**/Global", "Global", true),
+            ("**/Global", "a/b/Global", true),
+            ("saves/**", "saves/a/b/Chapter1", true),
+            ("saves/**", "saves", true),
+            ("saves/**/Map?", "saves/3ds/Map1", true),
+            ("saves/**/Map?", "other/3ds/Map1", false),
+        ];
+
+        for (pattern, path, expected) in cases {
+            assert_eq!(matches(pattern, path), expected, "{} {}", pattern, path);
+        }
+    }
+
+    #[test]
+    fn question_marks_match_characters() {
+        let cases = [
+            ("Sauvegarde?", "Sauvegardé", false),
+            ("Sauvegard?", "Sauvegardé", true),
+            ("??", "é", false),
+            ("?", "セ", true),
+            ("セーブ*", "セーブ1", true),
+        ];
+
+        for (pattern, name, expected) in cases {
+            assert_eq!(matches(pattern, name), expected, "{} {}", pattern, name);
+        }
+    }
+}
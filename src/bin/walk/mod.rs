@@ -0,0 +1,179 @@
+mod glob;
+
+use crate::args::{Options, DEFAULT_ARCHIVE_DIR, DEFAULT_BACKUP_DIR, STDIO};
+use crate::commands::Failure;
+
+pub const ZIP_EXTENSION: &str = ".zip";
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone)]
+pub struct Input {
+    pub name: String,
+    pub relative: Option<PathBuf>, // NOTE: Only set for files found while walking a directory, relative to it
+}
+
+impl Input {
+    pub fn is_walked(&self) -> bool {
+        self.relative.is_some()
+    }
+
+    pub fn is_zip(&self) -> bool {
+        self.name.to_ascii_lowercase().ends_with(ZIP_EXTENSION)
+    }
+}
+
+pub fn collect_inputs(options: &Options) -> (Vec<Input>, Vec<(String, Failure)>) {
+    let mut inputs = vec![];
+    let mut errors = vec![];
+
+    for file in &options.files {
+        let path = Path::new(file);
+
+        if file == STDIO || path.is_file() {
+            inputs.push(Input {
+                name: file.to_string(),
+                relative: None,
+            });
+        } else if path.is_dir() && options.recursive {
+            if let Err(error) = walk_dir(path, path, options, &mut inputs) {
+                errors.push((file.to_string(), error));
+            }
+        } else if path.is_dir() {
+            errors.push((
+                file.to_string(),
+                Failure::new(
+                    "is_directory",
+                    "Is a directory, use '--recursive' to process it".to_string(),
+                ),
+            ));
+        } else {
+            errors.push((
+                file.to_string(),
+                Failure::new("not_found", "File not found".to_string()),
+            ));
+        }
+    }
+
+    (inputs, errors)
+}
+
+pub fn collect_folders(options: &Options) -> (Vec<Input>, Vec<(String, Failure)>) {
+    let mut inputs = vec![];
+    let mut errors = vec![];
+
+    for file in &options.files {
+        let path = Path::new(file);
+
+        if path.is_dir() {
+            inputs.push(Input {
+                name: file.to_string(),
+                relative: None,
+            });
+        } else if path.exists() {
+            errors.push((
+                file.to_string(),
+                Failure::new("not_a_directory", "Is not a backup folder".to_string()),
+            ));
+        } else {
+            errors.push((
+                file.to_string(),
+                Failure::new("not_found", "Folder not found".to_string()),
+            ));
+        }
+    }
+
+    (inputs, errors)
+}
+
+pub fn walk_dir(
+    root: &Path,
+    dir: &Path,
+    options: &Options,
+    inputs: &mut Vec<Input>,
+) -> Result<(), Failure> {
+    let mut entries = fs::read_dir(dir)
+        .map_err(|e| {
+            Failure::new(
+                "read_error",
+                format!("Couldn't read '{}': {}", dir.display(), e),
+            )
+        })?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect::<Vec<PathBuf>>();
+
+    entries.sort(); // NOTE: "read_dir" has no order of its own, and the same tree should always be processed the same way
+
+    for path in entries {
+        // NOTE: Linked directories are never followed, a link back up the tree would otherwise be walked forever
+        if fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+            && path.is_dir()
+        {
+            continue;
+        }
+
+        if path.is_dir() {
+            if is_own_dir(&path, options) {
+                continue;
+            }
+
+            walk_dir(root, &path, options, inputs)?;
+        } else if path.is_file() {
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+
+            if is_included(&relative, options) {
+                inputs.push(Input {
+                    name: path.to_string_lossy().to_string(),
+                    relative: Some(relative),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// NOTE: Backups and the archive are never something to process again, wherever they were put
+fn is_own_dir(path: &Path, options: &Options) -> bool {
+    if path
+        .file_name()
+        .is_some_and(|name| name == DEFAULT_BACKUP_DIR || name == DEFAULT_ARCHIVE_DIR)
+    {
+        return true;
+    }
+
+    let Ok(path) = fs::canonicalize(path) else {
+        return false;
+    };
+
+    options
+        .backup_dir
+        .iter()
+        .map(String::as_str)
+        .chain([options.archive_dir()])
+        .filter_map(|dir| fs::canonicalize(dir).ok())
+        .any(|dir| dir == path)
+}
+
+pub fn is_included(relative: &Path, options: &Options) -> bool {
+    let relative = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let included = options.include.is_empty()
+        || options
+            .include
+            .iter()
+            .any(|pattern| glob::matches(pattern, &relative));
+    let excluded = options
+        .exclude
+        .iter()
+        .any(|pattern| glob::matches(pattern, &relative));
+
+    included && !excluded
+}
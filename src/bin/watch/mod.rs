@@ -0,0 +1,269 @@
+use crate::args::{Options, DEFAULT_BACKUP_DIR};
+use crate::commands::{backup, process_data, read_file, Failure, Outcome, Report};
+use crate::output::Mode;
+use crate::walk::{is_included, walk_dir, Input};
+use fest::{FESData, WriteOptions};
+use log::{debug, info, warn};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs,
+    hash::{Hash, Hasher},
+    path::{self, Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+struct Watch<'a> {
+    save_dir: PathBuf,
+    mirror_dir: PathBuf,
+    options: &'a Options,
+    written: HashMap<PathBuf, u64>, // NOTE: What was last written to each file, so the events caused by writing them aren't processed again
+}
+
+// NOTE: Saves in the watched directory are decompressed into the mirror with the same names and layout, and with "--recompress" edited files in the mirror go back the other way
+pub fn watch<F>(options: &Options, mut report: F) -> Result<(), String>
+where
+    F: FnMut(&Input, Result<Outcome, Failure>),
+{
+    let save_dir = options.files.first().ok_or("No directory given")?;
+    let mirror_dir = options
+        .out_dir
+        .as_ref()
+        .ok_or("Watching needs '--out-dir' for the mirror directory")?;
+
+    if !options.dry_run {
+        fs::create_dir_all(mirror_dir)
+            .map_err(|e| format!("Couldn't create '{}': {}", mirror_dir, e))?;
+    }
+
+    let save_dir =
+        fs::canonicalize(save_dir).map_err(|e| format!("Couldn't watch '{}': {}", save_dir, e))?;
+    let mirror_dir = match fs::canonicalize(mirror_dir) {
+        Ok(dir) => dir,
+        // NOTE: Dry runs don't make the mirror, so it may not be there yet
+        Err(_) if options.dry_run => path::absolute(mirror_dir)
+            .map_err(|e| format!("Couldn't watch '{}': {}", mirror_dir, e))?,
+        Err(e) => return Err(format!("Couldn't watch '{}': {}", mirror_dir, e)),
+    };
+
+    if save_dir.starts_with(&mirror_dir) || mirror_dir.starts_with(&save_dir) {
+        return Err(
+            "The mirror directory can't be inside the watched one, or the other way around"
+                .to_string(),
+        );
+    }
+
+    let mut watch = Watch {
+        save_dir,
+        mirror_dir,
+        options,
+        written: HashMap::new(),
+    };
+
+    let (sender, receiver) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(sender).map_err(|e| e.to_string())?;
+
+    watcher
+        .watch(&watch.save_dir, RecursiveMode::Recursive)
+        .map_err(|e| format!("Couldn't watch '{}': {}", watch.save_dir.display(), e))?;
+
+    if options.recompress && watch.mirror_dir.is_dir() {
+        watcher
+            .watch(&watch.mirror_dir, RecursiveMode::Recursive)
+            .map_err(|e| format!("Couldn't watch '{}': {}", watch.mirror_dir.display(), e))?;
+    }
+
+    info!(
+        "Watching '{}' into '{}'",
+        watch.save_dir.display(),
+        watch.mirror_dir.display()
+    );
+
+    let debounce = Duration::from_millis(options.debounce);
+    let mut pending = watch.outdated()?;
+
+    loop {
+        // NOTE: Files are only processed once they stopped changing for "debounce", so a save that is still being written isn't read halfway through
+        let timeout = pending
+            .values()
+            .map(|&changed: &Instant| {
+                (changed + debounce).saturating_duration_since(Instant::now())
+            })
+            .min()
+            .unwrap_or(IDLE_TIMEOUT);
+
+        match receiver.recv_timeout(timeout) {
+            Ok(Ok(event)) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                for path in event.paths {
+                    pending.insert(path, Instant::now());
+                }
+            }
+            Ok(Ok(_)) | Err(RecvTimeoutError::Timeout) => {}
+            Ok(Err(error)) => warn!("Error while watching: {}", error),
+            Err(RecvTimeoutError::Disconnected) => return Err("Stopped watching".to_string()),
+        }
+
+        let mut ready = pending
+            .iter()
+            .filter(|(_, &changed)| changed.elapsed() >= debounce)
+            .map(|(path, _)| path.to_path_buf())
+            .collect::<Vec<PathBuf>>();
+        ready.sort();
+
+        for path in ready {
+            pending.remove(&path);
+
+            if let Some((input, result)) = watch.sync(&path) {
+                report(&input, result);
+            }
+        }
+    }
+}
+
+impl Watch<'_> {
+    // NOTE: Saves that changed while nobody was watching, which are the ones without a mirror or with an older one
+    fn outdated(&self) -> Result<HashMap<PathBuf, Instant>, String> {
+        let mut inputs = vec![];
+        walk_dir(&self.save_dir, &self.save_dir, self.options, &mut inputs)
+            .map_err(|e| e.message)?;
+
+        let modified = |path: &Path| {
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        };
+        let long_ago = Instant::now()
+            .checked_sub(Duration::from_millis(self.options.debounce))
+            .unwrap_or(Instant::now());
+
+        Ok(inputs
+            .into_iter()
+            .filter_map(|input| {
+                let path = PathBuf::from(&input.name);
+                let mirror = self.mirror_dir.join(input.relative?);
+
+                match (modified(&path), modified(&mirror)) {
+                    (Some(save), Some(mirror)) if save <= mirror => None,
+                    _ => Some((path, long_ago)),
+                }
+            })
+            .collect())
+    }
+
+    fn sync(&mut self, path: &Path) -> Option<(Input, Result<Outcome, Failure>)> {
+        let (mode, relative, target) = if let Ok(relative) = path.strip_prefix(&self.save_dir) {
+            (Mode::Decompress, relative, self.mirror_dir.join(relative))
+        } else if let Ok(relative) = path.strip_prefix(&self.mirror_dir) {
+            (Mode::Compress, relative, self.save_dir.join(relative))
+        } else {
+            return None;
+        };
+
+        // NOTE: Temporary files from atomic writes and backups show up here too, and are never saves
+        let ignored = relative.components().any(|component| {
+            let name = component.as_os_str().to_string_lossy();
+            name.starts_with('.') || name == DEFAULT_BACKUP_DIR
+        });
+
+        if ignored || !path.is_file() || !is_included(relative, self.options) {
+            return None;
+        }
+
+        let input = Input {
+            name: path.to_string_lossy().to_string(),
+            relative: Some(relative.to_path_buf()),
+        };
+
+        let raw = match read_file(&input.name) {
+            Ok(raw) => raw,
+            Err(error) => return Some((input, Err(error))),
+        };
+
+        if self.written.get(path) == Some(&hash(&raw)) {
+            debug!("'{}' was written by this watch, ignoring it", input.name);
+            return None;
+        }
+
+        let file_data = match process_data(&raw, &input.name, self.options) {
+            Ok(file_data) => file_data,
+            Err(error) => {
+                debug!("Ignoring '{}': {}", input.name, error);
+                return None;
+            }
+        };
+
+        // NOTE: Only compressed saves are mirrored, and only decompressed files are sent back
+        match (mode, file_data.is_compressed) {
+            (Mode::Decompress, false) => {
+                debug!("Ignoring '{}', it is already decompressed", input.name);
+                return None;
+            }
+            (Mode::Compress, true) => {
+                debug!("Ignoring '{}', it is already compressed", input.name);
+                return None;
+            }
+            _ => {}
+        }
+
+        let result = self.write(file_data, mode, &target);
+
+        Some((input, result))
+    }
+
+    fn write(&mut self, file_data: FESData, mode: Mode, target: &Path) -> Result<Outcome, Failure> {
+        let mut report = Report::new(&file_data);
+
+        let file_data = match mode {
+            Mode::Decompress => file_data.decompress()?,
+            Mode::Compress => file_data.compress()?,
+        };
+
+        match mode {
+            Mode::Decompress => report.plain_size = Some(file_data.raw.len()),
+            Mode::Compress => report.compressed_size = Some(file_data.raw.len()),
+        }
+        report.output = Some(target.to_string_lossy().to_string());
+
+        if self.options.dry_run {
+            report.message = format!("Would write '{}'", target.display());
+            return Ok(Outcome::Done(report));
+        }
+
+        if let Some(parent) = target.parent().filter(|parent| !parent.exists()) {
+            fs::create_dir_all(parent).map_err(|e| Failure::new("write_error", e.to_string()))?;
+        }
+
+        // NOTE: The mirror can always be made again, but the saves going back may be the only copy
+        report.backup = match mode == Mode::Compress && self.options.backup && target.exists() {
+            true => backup(target, self.options)?,
+            false => None,
+        };
+
+        // NOTE: The mirror is this watch's own output and is rewritten on every change, while saves follow "--force" like everywhere else
+        let write_options = match mode {
+            Mode::Decompress => WriteOptions {
+                no_clobber: false,
+                sync: self.options.sync,
+            },
+            Mode::Compress => self.options.write_options(),
+        };
+
+        file_data.write_with(target, write_options)?;
+        self.written
+            .insert(target.to_path_buf(), hash(&file_data.raw));
+
+        report.message = format!("Wrote '{}'", target.display());
+
+        Ok(Outcome::Done(report))
+    }
+}
+
+fn hash(raw: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    raw.hash(&mut hasher);
+
+    hasher.finish()
+}
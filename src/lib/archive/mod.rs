@@ -0,0 +1,540 @@
+use crate::constants::FESTError;
+use crate::utils::timestamp;
+use crate::write::{write_atomic, WriteOptions};
+use crate::{FESData, GameVariant, SaveKind};
+use sha2::{Digest, Sha256};
+use std::{
+    fmt::Write as _,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+// Layout of an archive directory:
+// objects/<first 2 hex digits>/<other 62 hex digits>: Decompressed saves, named after the SHA-256 of their contents
+// history: One line for each version added, with tab separated fields in the order of "ArchiveEntry"
+
+const OBJECTS_DIR: &str = "objects";
+const HISTORY_FILE: &str = "history";
+const NONE_FIELD: &str = "-";
+const MIN_PREFIX_LEN: usize = 4;
+const HASH_LEN: usize = 64;
+const FIELD_COUNT: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    pub added: String, // NOTE: As "YYYYMMDDTHHMMSSZ", like backups
+    pub hash: String,
+    pub kind: SaveKind,
+    pub slot: Option<u8>,
+    pub variant: Option<GameVariant>,
+    pub is_compressed: bool, // NOTE: The form it was added in, which is the one it is restored in
+    pub comp_version: Option<u32>, // NOTE: From the COMP header it had, so compressing it again gives the same one
+    pub header_len: usize, // NOTE: Saves found with "--scan" can have any, so it isn't probed again
+    pub plain_size: usize,
+    pub source: String,
+}
+
+impl ArchiveEntry {
+    // NOTE: Like "Chapter1" or "Global", which saves of different games share
+    pub fn slot_name(&self) -> String {
+        match self.slot {
+            Some(slot) => format!("{}{}", self.kind, slot),
+            None => self.kind.to_string(),
+        }
+    }
+
+    // NOTE: Versions of the same save share this, like "Awakening Chapter1", no matter where they were added from
+    pub fn slot_key(&self) -> String {
+        let game = self
+            .variant
+            .map(|variant| variant.to_string())
+            .unwrap_or("Unknown".to_string());
+
+        format!("{} {}", game, self.slot_name())
+    }
+
+    fn to_line(&self) -> String {
+        [
+            self.added.to_string(),
+            self.hash.to_string(),
+            self.kind.to_string(),
+            self.slot
+                .map(|slot| slot.to_string())
+                .unwrap_or(NONE_FIELD.to_string()),
+            self.variant
+                .map(|variant| variant.to_string())
+                .unwrap_or(NONE_FIELD.to_string()),
+            match self.is_compressed {
+                true => "compressed",
+                false => "decompressed",
+            }
+            .to_string(),
+            optional(self.comp_version),
+            self.header_len.to_string(),
+            self.plain_size.to_string(),
+            self.source.replace(['\t', '\n'], " "),
+        ]
+        .join("\t")
+    }
+
+    fn from_line(line: &str) -> Result<Self, FESTError> {
+        let invalid = || FESTError::ArchiveError(format!("Invalid history line '{}'", line));
+        let fields = line.splitn(FIELD_COUNT, '\t').collect::<Vec<&str>>();
+
+        let [added, hash, kind, slot, variant, state, comp_version, header_len, plain_size, source] =
+            fields[..]
+        else {
+            return Err(invalid());
+        };
+
+        // NOTE: Everything reading objects expects a full hash, so a damaged line is rejected here instead
+        if hash.len() != HASH_LEN || !hash.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')) {
+            return Err(invalid());
+        }
+
+        let number = |field: &str| match field {
+            NONE_FIELD => Ok(None),
+            field => field.parse().map(Some).map_err(|_| invalid()),
+        };
+
+        Ok(ArchiveEntry {
+            added: added.to_string(),
+            hash: hash.to_string(),
+            kind: kind.parse()?,
+            slot: match slot {
+                NONE_FIELD => None,
+                slot => Some(slot.parse().map_err(|_| invalid())?),
+            },
+            variant: match variant {
+                NONE_FIELD => None,
+                variant => Some(variant.parse()?),
+            },
+            is_compressed: match state {
+                "compressed" => true,
+                "decompressed" => false,
+                _ => return Err(invalid()),
+            },
+            comp_version: number(comp_version)?,
+            header_len: header_len.parse().map_err(|_| invalid())?,
+            plain_size: plain_size.parse().map_err(|_| invalid())?,
+            source: source.to_string(),
+        })
+    }
+}
+
+// NOTE: Saves are stored decompressed and named after their contents, so the same save compressed with a different tree is still only stored once
+#[derive(Debug, Clone)]
+pub struct SaveArchive {
+    root: PathBuf,
+}
+
+impl SaveArchive {
+    pub fn open<P: AsRef<Path>>(root: P) -> Self {
+        SaveArchive {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    // NOTE: Gives back the new entry, or the latest one of the same slot if it already had these contents, along with whether it was added
+    pub fn add(
+        &self,
+        file_data: &FESData,
+        source: &str,
+    ) -> Result<(ArchiveEntry, bool), FESTError> {
+        let (entry, decompressed) = match self.prepare(file_data, source)? {
+            (entry, Some(decompressed)) => (entry, decompressed),
+            (latest, None) => return Ok((latest, false)),
+        };
+
+        let object_path = self.object_path(&entry.hash);
+        if !object_path.is_file() {
+            if let Some(parent) = object_path.parent() {
+                fs::create_dir_all(parent).map_err(|e| FESTError::WriteError(e.to_string()))?;
+            }
+
+            // NOTE: Another process storing the same contents at the same time writes the same bytes, so losing that race is fine
+            match write_atomic(
+                &object_path,
+                &decompressed.raw,
+                WriteOptions {
+                    no_clobber: true,
+                    sync: true,
+                },
+            ) {
+                Err(FESTError::FileExists(_)) => {}
+                result => result?,
+            }
+        }
+
+        let mut history = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.root.join(HISTORY_FILE))
+            .map_err(|e| FESTError::WriteError(e.to_string()))?;
+        history
+            .write_all(format!("{}\n", entry.to_line()).as_bytes())
+            .map_err(|e| FESTError::WriteError(e.to_string()))?;
+
+        Ok((entry, true))
+    }
+
+    // NOTE: Same as "add", decompressing and hashing the save, but without storing anything
+    pub fn preview(
+        &self,
+        file_data: &FESData,
+        source: &str,
+    ) -> Result<(ArchiveEntry, bool), FESTError> {
+        let (entry, decompressed) = self.prepare(file_data, source)?;
+
+        Ok((entry, decompressed.is_some()))
+    }
+
+    // NOTE: The decompressed save is only given back when it isn't already the latest version of its slot
+    fn prepare(
+        &self,
+        file_data: &FESData,
+        source: &str,
+    ) -> Result<(ArchiveEntry, Option<FESData>), FESTError> {
+        let decompressed = file_data.clone().decompress()?;
+        let hash = hash(&decompressed.raw);
+
+        let entry = ArchiveEntry {
+            added: timestamp(SystemTime::now()),
+            hash,
+            kind: file_data.kind(),
+            slot: SaveKind::slot_from_file_name(source),
+            variant: file_data.variant(),
+            is_compressed: file_data.is_compressed,
+            comp_version: decompressed
+                .comp_header()
+                .map(|comp_header| comp_header.version),
+            header_len: decompressed.header_len(),
+            plain_size: decompressed.raw.len(),
+            source: source.to_string(),
+        };
+
+        let latest = self
+            .history()?
+            .into_iter()
+            .rev()
+            .find(|other| other.slot_key() == entry.slot_key());
+
+        match latest.filter(|latest| latest.hash == entry.hash) {
+            Some(latest) => Ok((latest, None)),
+            None => Ok((entry, Some(decompressed))),
+        }
+    }
+
+    // NOTE: Oldest first
+    pub fn history(&self) -> Result<Vec<ArchiveEntry>, FESTError> {
+        let history_path = self.root.join(HISTORY_FILE);
+        if !history_path.is_file() {
+            return Ok(vec![]);
+        }
+
+        fs::read_to_string(&history_path)
+            .map_err(|e| FESTError::ReadError(format!("'{}': {}", history_path.display(), e)))?
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(ArchiveEntry::from_line)
+            .collect()
+    }
+
+    // NOTE: Any unique start of a hash finds its latest entry, like abbreviated commits do in git
+    pub fn find(&self, prefix: &str) -> Result<ArchiveEntry, FESTError> {
+        let prefix = prefix.to_ascii_lowercase();
+        if prefix.len() < MIN_PREFIX_LEN || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(FESTError::ArchiveError(format!(
+                "'{}' isn't a hash, or the start of one with at least {} digits",
+                prefix, MIN_PREFIX_LEN
+            )));
+        }
+
+        let mut matches = self
+            .history()?
+            .into_iter()
+            .filter(|entry| entry.hash.starts_with(&prefix))
+            .collect::<Vec<ArchiveEntry>>();
+
+        if matches.iter().any(|entry| {
+            matches
+                .first()
+                .is_some_and(|first| first.hash != entry.hash)
+        }) {
+            return Err(FESTError::ArchiveError(format!(
+                "'{}' is the start of more than one hash",
+                prefix
+            )));
+        }
+
+        matches.pop().ok_or(FESTError::ArchiveError(format!(
+            "No version found for '{}'",
+            prefix
+        )))
+    }
+
+    // NOTE: Saves added compressed are compressed again, with a new tree but their COMP version, and checked before being given back
+    pub fn restore(&self, entry: &ArchiveEntry) -> Result<FESData, FESTError> {
+        let object_path = self.object_path(&entry.hash);
+        let raw = fs::read(&object_path)
+            .map_err(|e| FESTError::ReadError(format!("'{}': {}", object_path.display(), e)))?;
+
+        if hash(&raw) != entry.hash {
+            return Err(FESTError::ArchiveError(format!(
+                "'{}' doesn't match its hash anymore",
+                object_path.display()
+            )));
+        }
+
+        let mut file_data = FESData::process_data_at(&raw, entry.header_len, entry.kind)?;
+        if let Some(variant) = entry.variant {
+            file_data.set_variant(variant);
+        }
+        if let Some(comp_version) = entry.comp_version {
+            file_data.set_comp_version(comp_version);
+        }
+
+        if !entry.is_compressed {
+            return Ok(file_data);
+        }
+
+        let compressed = file_data.compress()?;
+        if !compressed.verify()? {
+            return Err(FESTError::ArchiveError(format!(
+                "'{}' doesn't pass its own checksum once compressed",
+                entry.hash
+            )));
+        }
+
+        Ok(compressed)
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        let (dir, name) = hash.split_at(2);
+
+        self.root.join(OBJECTS_DIR).join(dir).join(name)
+    }
+}
+
+fn optional(value: Option<u32>) -> String {
+    value
+        .map(|value| value.to_string())
+        .unwrap_or(NONE_FIELD.to_string())
+}
+
+fn hash(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .fold(String::new(), |mut hash, byte| {
+            let _ = write!(hash, "{:02x}", byte);
+            hash
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("fest-archive-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        dir
+    }
+
+    fn save(header_len: usize, block: &[u8]) -> FESData {
+        let mut raw = vec![0x11; header_len];
+        raw.extend(0x494E4445u32.to_le_bytes());
+        raw.extend(1u32.to_le_bytes());
+        raw.extend(0x554E4954u32.to_le_bytes());
+        raw.extend(20u32.to_le_bytes());
+        raw.extend((block.len() as u32).to_le_bytes());
+        raw.extend(block);
+
+        FESData::scan_data(&raw).unwrap()
+    }
+
+    fn entry() -> ArchiveEntry {
+        ArchiveEntry {
+            added: "20240102T030405Z".to_string(),
+            hash: "0123456789abcdef".repeat(4),
+            kind: SaveKind::Chapter,
+            slot: Some(2),
+            variant: Some(GameVariant::Fates),
+            is_compressed: true,
+            comp_version: Some(5),
+            header_len: 0xC0,
+            plain_size: 1234,
+            source: "/saves/Chapter2".to_string(),
+        }
+    }
+
+    #[test]
+    fn lines_round_trip() {
+        let entry = entry();
+        assert_eq!(ArchiveEntry::from_line(&entry.to_line()).unwrap(), entry);
+
+        let entry = ArchiveEntry {
+            slot: None,
+            variant: None,
+            is_compressed: false,
+            comp_version: None,
+            source: "/saves/Glo\tbal".to_string(),
+            ..entry
+        };
+        let parsed = ArchiveEntry::from_line(&entry.to_line()).unwrap();
+        assert_eq!(parsed.source, "/saves/Glo bal"); // NOTE: Tabs would split the line
+        assert_eq!(
+            parsed,
+            ArchiveEntry {
+                source: "/saves/Glo bal".to_string(),
+                ..entry
+            }
+        );
+    }
+
+    #[test]
+    fn bad_lines_are_rejected() {
+        let line = entry().to_line();
+
+        for hash in ["0123", &"0123456789ABCDEF".repeat(4), &"g".repeat(64)] {
+            let line = line.replace(&entry().hash, hash);
+            assert!(ArchiveEntry::from_line(&line).is_err(), "{}", hash);
+        }
+
+        let fields = line.split('\t').collect::<Vec<&str>>();
+        assert!(ArchiveEntry::from_line(&fields[..FIELD_COUNT - 1].join("\t")).is_err());
+        assert!(ArchiveEntry::from_line("").is_err());
+    }
+
+    #[test]
+    fn same_contents_are_stored_once() {
+        let dir = test_dir("dedup");
+        let archive = SaveArchive::open(&dir);
+        let first = save(0xC0, b"first");
+
+        let (entry, added) = archive.add(&first, "/saves/Chapter1").unwrap();
+        assert!(added);
+        assert_eq!(entry.slot_key(), "Unknown Chapter1");
+
+        let (same, added) = archive.add(&first, "/other/Chapter1").unwrap();
+        assert!(!added);
+        assert_eq!(same, entry);
+
+        // NOTE: Going back to older contents is a new version, even if they are stored already
+        assert!(
+            archive
+                .add(&save(0xC0, b"second"), "/saves/Chapter1")
+                .unwrap()
+                .1
+        );
+        assert!(archive.add(&first, "/saves/Chapter1").unwrap().1);
+
+        // NOTE: The same contents of another game are another slot
+        let mut fates = first.clone();
+        fates.set_variant(GameVariant::Fates);
+        assert!(archive.add(&fates, "/saves/Chapter1").unwrap().1);
+
+        assert_eq!(archive.history().unwrap().len(), 4);
+        assert_eq!(fs::read_dir(dir.join(OBJECTS_DIR)).unwrap().count(), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn previews_store_nothing() {
+        let dir = test_dir("preview");
+        let archive = SaveArchive::open(&dir);
+        let first = save(0xC0, b"first");
+
+        let (entry, added) = archive.preview(&first, "/saves/Chapter1").unwrap();
+        assert!(added);
+        assert!(archive.history().unwrap().is_empty());
+        assert!(!dir.join(OBJECTS_DIR).exists());
+
+        let (stored, _) = archive.add(&first, "/saves/Chapter1").unwrap();
+        assert_eq!(stored.hash, entry.hash);
+
+        let (same, added) = archive.preview(&first, "/saves/Chapter1").unwrap();
+        assert!(!added);
+        assert_eq!(same, stored);
+        assert!(
+            archive
+                .preview(&save(0xC0, b"second"), "/saves/Chapter1")
+                .unwrap()
+                .1
+        );
+        assert_eq!(archive.history().unwrap().len(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn versions_are_found_by_prefix() {
+        let dir = test_dir("find");
+        let archive = SaveArchive::open(&dir);
+        let (entry, _) = archive
+            .add(&save(0xC0, b"first"), "/saves/Chapter1")
+            .unwrap();
+
+        assert_eq!(archive.find(&entry.hash[..MIN_PREFIX_LEN]).unwrap(), entry);
+        assert_eq!(archive.find(&entry.hash.to_uppercase()).unwrap(), entry);
+        assert!(archive.find(&entry.hash[..MIN_PREFIX_LEN - 1]).is_err());
+        assert!(archive.find("not a hash").is_err());
+
+        let other = match entry.hash.starts_with("0000") {
+            true => "1111",
+            false => "0000",
+        };
+        assert!(archive.find(other).is_err());
+
+        // NOTE: Two hashes starting the same way
+        let mut history = OpenOptions::new()
+            .append(true)
+            .open(dir.join(HISTORY_FILE))
+            .unwrap();
+        for end in ["0", "1"] {
+            let line = ArchiveEntry {
+                hash: format!("{}{}", "abcd".repeat(15), end.repeat(4)),
+                ..entry.clone()
+            }
+            .to_line();
+            writeln!(history, "{}", line).unwrap();
+        }
+        assert!(archive.find("abcdabcd").is_err());
+        assert!(archive.find(&format!("{}1", "abcd".repeat(15))).is_ok());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn restores_what_was_added() {
+        let dir = test_dir("restore");
+        let archive = SaveArchive::open(&dir);
+
+        // NOTE: A preamble only "--scan" finds
+        let scanned = save(0x40, b"scanned");
+        let (entry, _) = archive.add(&scanned, "/saves/Chapter1").unwrap();
+        assert_eq!(entry.header_len, 0x40);
+        assert_eq!(archive.restore(&entry).unwrap().raw, scanned.raw);
+
+        let mut decompressed = save(0xC0, b"compressed").decompress().unwrap();
+        decompressed.set_comp_version(5);
+        let compressed = decompressed.compress().unwrap();
+        let (entry, _) = archive.add(&compressed, "/saves/Chapter2").unwrap();
+        assert_eq!(entry.comp_version, Some(5));
+
+        let restored = archive.restore(&entry).unwrap();
+        assert!(restored.is_compressed);
+        assert_eq!(restored.comp_header().unwrap().version, 5);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,98 @@
+use crate::checksum::get_checksum;
+use crate::constants::FESTError;
+use crate::utils::timestamp;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+// NOTE: Backups are named "<file name>.<YYYYMMDDTHHMMSSZ>.<checksum>", so they sort by age and the same content is never stored twice in the same second
+pub fn backup_file(
+    path: &Path,
+    backup_dir: &Path,
+    keep: Option<usize>,
+) -> Result<Option<PathBuf>, FESTError> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let name = path
+        .file_name()
+        .ok_or(FESTError::BackupError(format!(
+            "'{}' has no file name",
+            path.display()
+        )))?
+        .to_string_lossy()
+        .to_string();
+
+    let raw = fs::read(path).map_err(|e| FESTError::BackupError(e.to_string()))?;
+    let backup_path = backup_dir.join(format!(
+        "{}.{}.{:08x}",
+        name,
+        timestamp(SystemTime::now()),
+        get_checksum(0, &raw)?
+    ));
+
+    fs::create_dir_all(backup_dir).map_err(|e| FESTError::BackupError(e.to_string()))?;
+
+    // NOTE: Copying instead of moving keeps the original in place until the new file is actually written over it
+    if !backup_path.exists() {
+        fs::copy(path, &backup_path).map_err(|e| FESTError::BackupError(e.to_string()))?;
+    }
+
+    if let Some(keep) = keep {
+        prune_backups(backup_dir, &name, keep)?;
+    }
+
+    Ok(Some(backup_path))
+}
+
+pub fn list_backups(backup_dir: &Path, name: &str) -> Result<Vec<PathBuf>, FESTError> {
+    if !backup_dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut backups = fs::read_dir(backup_dir)
+        .map_err(|e| FESTError::BackupError(e.to_string()))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|file_name| file_name.to_str())
+                .and_then(|file_name| file_name.strip_prefix(name))
+                .and_then(|suffix| suffix.strip_prefix('.'))
+                .is_some_and(is_backup_suffix)
+        })
+        .collect::<Vec<PathBuf>>();
+
+    backups.sort();
+
+    Ok(backups)
+}
+
+fn prune_backups(backup_dir: &Path, name: &str, keep: usize) -> Result<(), FESTError> {
+    let backups = list_backups(backup_dir, name)?;
+    let excess = backups.len().saturating_sub(keep);
+
+    for backup in backups.iter().take(excess) {
+        fs::remove_file(backup).map_err(|e| FESTError::BackupError(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+fn is_backup_suffix(suffix: &str) -> bool {
+    let Some((time, checksum)) = suffix.split_once('.') else {
+        return false;
+    };
+
+    let time = time.as_bytes();
+
+    time.len() == 16
+        && time[8] == b'T'
+        && time[15] == b'Z'
+        && time[..8].iter().chain(&time[9..15]).all(u8::is_ascii_digit)
+        && checksum.len() == 8
+        && checksum.chars().all(|c| c.is_ascii_hexdigit())
+}
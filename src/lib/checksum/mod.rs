@@ -1,4 +1,5 @@
 use crate::constants::FESTError;
+use log::trace;
 
 const TABLE: [u32; 256] = [
     0x00000000, 0x77073096, 0xee0e612c, 0x990951ba, 0x076dc419, 0x706af48f, 0xe963a535, 0x9e6495a3,
@@ -104,6 +105,6 @@ pub fn get_checksum(old_checksum: u32, data: &[u8]) -> Result<u32, FESTError> {
         }
     };
 
-    println!("{}", !checksum);
+    trace!("Checksum of {} bytes is {:#010X}", data.len(), !checksum);
     Ok(!checksum)
 }
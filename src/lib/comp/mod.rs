@@ -0,0 +1,51 @@
+use crate::constants::{FESTError, COMP_MAGIC};
+use crate::layout::{COMP_CHECKSUM, COMP_LENGTH, COMP_MAGIC_FIELD, COMP_VERSION};
+use crate::utils::from_uint32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompHeader {
+    pub version: u32,
+    pub length: u32,
+    pub checksum: u32,
+}
+
+impl CompHeader {
+    pub const DEFAULT_VERSION: u32 = 2;
+
+    pub fn parse(data: &[u8]) -> Result<Self, FESTError> {
+        if COMP_MAGIC_FIELD.read(data, 0)? != COMP_MAGIC {
+            return Err(FESTError::InvalidData);
+        }
+
+        Ok(CompHeader {
+            version: COMP_VERSION.read(data, 0)?,
+            length: COMP_LENGTH.read(data, 0)?,
+            checksum: COMP_CHECKSUM.read(data, 0)?,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut raw = vec![];
+        raw.extend(from_uint32(COMP_MAGIC));
+        raw.extend(from_uint32(self.version));
+        raw.extend(from_uint32(self.length));
+        raw.extend(from_uint32(self.checksum));
+
+        raw
+    }
+}
+
+// NOTE: What is needed to encode the data exactly as it was found: the original tree, and whatever was left after the last code word, which only belongs to the data it was found with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompEncoding {
+    pub code_tree: Vec<u8>,
+    pub padding: Vec<u8>,
+    pub data: Vec<u8>,
+}
+
+// NOTE: "size" is how many bytes the serialized tree takes, counting the first one that holds its length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeInfo {
+    pub size: usize,
+    pub symbols: usize,
+}
@@ -2,6 +2,12 @@
 pub const COMP_MAGIC: u32 = 0x434F4D50;
 pub const INDE_MAGIC: u32 = 0x494E4445;
 
+pub const CHAPTER_HEADER_LEN: usize = 0xC0;
+pub const COMP_HEADER_LEN: usize = 0x10;
+
+pub const SCAN_ALIGNMENT: usize = 4;
+pub const SCAN_LIMIT: usize = 0x1000;
+
 pub const CMD_CODE: u32 = 0x28;
 
 pub const HUF_SHIFT: u8 = 1;
@@ -17,6 +23,9 @@ pub const HUF_NEXT: u8 = 0x3F;
 
 pub const HUF_TREEOFS: usize = 4;
 
+pub const INDE_ENTRIES_OFS: usize = 8;
+pub const INDE_ENTRY_SIZE: usize = 12;
+
 // NOTE: "HUF_MAXSYMBOLS" was removed since it was just "0xFF + 1" instead of being a magic number
 // NOTE: "HUF_MAXIM" was removed since it was only used in "pbuf" during compression, but since it is growable instead of fixed-size, this constant doesn't need to exist
 
@@ -29,8 +38,14 @@ pub enum FESTError {
     InvalidData,
     #[error("Error when generating checksum: {0}")]
     ChecksumError(String),
+    #[error("Error when reading data: {0}")]
+    ReadError(String),
     #[error("Error when writing to file: {0}")]
     WriteError(String),
+    #[error("File already exists: {0}")]
+    FileExists(String),
+    #[error("Error when backing up file: {0}")]
+    BackupError(String),
     #[error("Not 32-bit target or higher, won't work")]
     UnsuportedArchitecture,
     #[error("Error transforming bytes of length {0} on offset {1}")]
@@ -51,4 +66,55 @@ pub enum FESTError {
     CodeTreeIOOB(String, usize),
     #[error("Index out of bounds for code_mask at {0} when length is {1}")]
     CodeMaskIOOB(String, usize),
+    #[error("Operation needs decompressed data")]
+    NotDecompressed,
+    #[error("Index out of bounds for save index at {0} when length is {1}")]
+    SaveIndexIOOB(usize, usize),
+    #[error("Block {0:#010X} overlaps another block or the save index")]
+    BlockOverlap(u32),
+    #[error("Block not found: {0:#010X}")]
+    BlockNotFound(u32),
+    #[error("Unknown game variant: {0}")]
+    UnknownVariant(String),
+    #[error("Neither \"COMP\" nor \"INDE\" magic found in the first {0:#X} bytes")]
+    MagicNotFound(usize),
+    #[error("Unknown save kind: {0}")]
+    UnknownKind(String),
+    #[error("Error in ZIP archive: {0}")]
+    ZipError(String),
+    #[error("Error in save folder: {0}")]
+    FolderError(String),
+    #[error("Error in save archive: {0}")]
+    ArchiveError(String),
+}
+
+impl FESTError {
+    // NOTE: A stable name for each kind of error, for whoever needs to tell them apart without parsing the messages
+    pub fn code(&self) -> &'static str {
+        match self {
+            FESTError::InvalidData | FESTError::MagicNotFound(_) => "invalid_data",
+            FESTError::ChecksumError(_) => "checksum_error",
+            FESTError::ReadError(_) => "read_error",
+            FESTError::WriteError(_) => "write_error",
+            FESTError::FileExists(_) => "file_exists",
+            FESTError::BackupError(_) => "backup_error",
+            FESTError::UnsuportedArchitecture => "unsupported_architecture",
+            FESTError::BytesToU32Error(..) | FESTError::DecompressDataIOOB(..) => "corrupt_data",
+            FESTError::FreqOverflow(_)
+            | FESTError::NodeNotFound(_)
+            | FESTError::CodeNotFound(_)
+            | FESTError::CodeWorkNotFound(_)
+            | FESTError::WorkNotFound(_)
+            | FESTError::CodeTreeIOOB(..)
+            | FESTError::CodeMaskIOOB(..) => "compression_error",
+            FESTError::NotDecompressed => "not_decompressed",
+            FESTError::SaveIndexIOOB(..)
+            | FESTError::BlockOverlap(_)
+            | FESTError::BlockNotFound(_) => "invalid_index",
+            FESTError::UnknownVariant(_) | FESTError::UnknownKind(_) => "unknown_value",
+            FESTError::ZipError(_) => "zip_error",
+            FESTError::FolderError(_) => "folder_error",
+            FESTError::ArchiveError(_) => "archive_error",
+        }
+    }
 }
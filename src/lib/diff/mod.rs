@@ -0,0 +1,147 @@
+use crate::constants::FESTError;
+use crate::index::block_name;
+use crate::layout::{Section, SectionKind};
+use crate::FESData;
+
+// NOTE: "offset" is relative to the start of the region, and at the end of a region that changed size one side can be shorter or empty
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ByteRange {
+    pub offset: usize,
+    pub old: Vec<u8>,
+    pub new: Vec<u8>,
+}
+
+// NOTE: Regions are the sections of the decompressed data, offsets are where they start in it and "None" when they only exist on one side. "name" is the name of the block a region belongs to, if it has one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionDiff {
+    pub region: SectionKind,
+    pub name: Option<String>,
+    pub old_offset: Option<usize>,
+    pub new_offset: Option<usize>,
+    pub old_len: Option<usize>,
+    pub new_len: Option<usize>,
+    pub changes: Vec<ByteRange>,
+}
+
+// NOTE: Both saves are decompressed and compared section by section, which covers every byte of them. Sections are paired by what they are instead of by position, so a block growing doesn't make everything after it different. Only regions that changed are given back
+pub fn diff_saves(old: &FESData, new: &FESData) -> Result<Vec<RegionDiff>, FESTError> {
+    let old = old.clone().decompress()?;
+    let new = new.clone().decompress()?;
+
+    let old_sections = old.layout()?;
+    let new_sections = new.layout()?;
+
+    let region = |data: &FESData, section: &Section| {
+        let end = (section.offset + section.len).min(data.raw.len());
+
+        (
+            section.offset,
+            data.raw
+                .get(section.offset..end)
+                .unwrap_or_default()
+                .to_vec(),
+        )
+    };
+
+    let mut diffs = vec![];
+    let mut paired = vec![false; new_sections.len()];
+
+    for old_section in &old_sections {
+        let new_region = new_sections
+            .iter()
+            .enumerate()
+            .find(|&(i, new_section)| !paired[i] && new_section.kind == old_section.kind)
+            .map(|(i, new_section)| {
+                paired[i] = true;
+                region(&new, new_section)
+            });
+
+        diffs.extend(diff_region(
+            old_section.kind,
+            Some(region(&old, old_section)),
+            new_region,
+        ));
+    }
+
+    for (new_section, _) in new_sections
+        .iter()
+        .zip(paired)
+        .filter(|&(_, paired)| !paired)
+    {
+        diffs.extend(diff_region(
+            new_section.kind,
+            None,
+            Some(region(&new, new_section)),
+        ));
+    }
+
+    Ok(diffs)
+}
+
+fn diff_region(
+    region: SectionKind,
+    old: Option<(usize, Vec<u8>)>,
+    new: Option<(usize, Vec<u8>)>,
+) -> Option<RegionDiff> {
+    let changes = match (&old, &new) {
+        (Some((_, old)), Some((_, new))) => changed_ranges(old, new),
+        (None, None) => return None,
+        _ => vec![],
+    };
+
+    if changes.is_empty() && old.is_some() && new.is_some() {
+        return None;
+    }
+
+    let name = match region {
+        SectionKind::IndexEntry(id) | SectionKind::Block(id) | SectionKind::Padding(id) => {
+            block_name(id)
+        }
+        _ => None,
+    };
+
+    Some(RegionDiff {
+        region,
+        name,
+        old_offset: old.as_ref().map(|(offset, _)| *offset),
+        new_offset: new.as_ref().map(|(offset, _)| *offset),
+        old_len: old.as_ref().map(|(_, data)| data.len()),
+        new_len: new.as_ref().map(|(_, data)| data.len()),
+        changes,
+    })
+}
+
+// NOTE: Runs of differing bytes, where whatever is past the end of the shorter side counts as one last run
+fn changed_ranges(old: &[u8], new: &[u8]) -> Vec<ByteRange> {
+    let common = old.len().min(new.len());
+    let mut ranges = vec![];
+
+    let mut i = 0;
+    while i < common {
+        if old[i] == new[i] {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        while i < common && old[i] != new[i] {
+            i += 1;
+        }
+
+        ranges.push(ByteRange {
+            offset: start,
+            old: old[start..i].to_vec(),
+            new: new[start..i].to_vec(),
+        });
+    }
+
+    if old.len() != new.len() {
+        ranges.push(ByteRange {
+            offset: common,
+            old: old[common..].to_vec(),
+            new: new[common..].to_vec(),
+        });
+    }
+
+    ranges
+}
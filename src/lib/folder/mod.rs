@@ -0,0 +1,138 @@
+use crate::constants::FESTError;
+use crate::write::{write_dir_atomic, WriteOptions};
+use crate::FESData;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+// NOTE: Save managers like Checkpoint and JKSM back up a title as a folder with its raw save files, which is only restorable as a whole
+#[derive(Debug, Clone)]
+pub struct FolderEntry {
+    pub relative: PathBuf,
+    pub raw: Vec<u8>,
+    pub save: Option<FESData>, // NOTE: Files that aren't saves are kept as they are
+}
+
+#[derive(Debug, Clone)]
+pub struct SaveFolder {
+    pub entries: Vec<FolderEntry>,
+}
+
+impl SaveFolder {
+    pub fn read(dir: &Path) -> Result<Self, FESTError> {
+        let mut files = vec![];
+        list_files(dir, dir, &mut files)?;
+
+        let entries = files
+            .into_iter()
+            .map(|(path, relative)| {
+                let raw = fs::read(&path)
+                    .map_err(|e| FESTError::ReadError(format!("'{}': {}", path.display(), e)))?;
+                let save = FESData::process_file_data(&raw, &relative.to_string_lossy()).ok();
+
+                Ok(FolderEntry {
+                    relative,
+                    raw,
+                    save,
+                })
+            })
+            .collect::<Result<Vec<FolderEntry>, FESTError>>()?;
+
+        Ok(SaveFolder { entries })
+    }
+
+    pub fn saves(&self) -> impl Iterator<Item = (&Path, &FESData)> {
+        self.entries
+            .iter()
+            .filter_map(|entry| Some((entry.relative.as_path(), entry.save.as_ref()?)))
+    }
+
+    pub fn decompress(self) -> Result<Self, FESTError> {
+        self.map_saves(|save| save.decompress())
+    }
+
+    // NOTE: A folder is only restorable when every save in it is, so each one is verified and the first that isn't fails the whole folder
+    pub fn compress(self) -> Result<Self, FESTError> {
+        let folder = self.map_saves(|save| save.compress())?;
+
+        for (relative, save) in folder.saves() {
+            if !save.verify()? {
+                return Err(FESTError::FolderError(format!(
+                    "'{}' doesn't pass its own checksum",
+                    relative.display()
+                )));
+            }
+        }
+
+        Ok(folder)
+    }
+
+    pub fn write_to<P: AsRef<Path>>(&self, dir: P, options: WriteOptions) -> Result<(), FESTError> {
+        let files = self
+            .entries
+            .iter()
+            .map(|entry| (entry.relative.to_path_buf(), entry.raw.as_slice()))
+            .collect::<Vec<(PathBuf, &[u8])>>();
+
+        write_dir_atomic(dir.as_ref(), &files, options)
+    }
+
+    fn map_saves<F>(self, mut convert: F) -> Result<Self, FESTError>
+    where
+        F: FnMut(FESData) -> Result<FESData, FESTError>,
+    {
+        let entries = self
+            .entries
+            .into_iter()
+            .map(|entry| match entry.save {
+                Some(save) => {
+                    let save = convert(save).map_err(|e| {
+                        FESTError::FolderError(format!("'{}': {}", entry.relative.display(), e))
+                    })?;
+
+                    Ok(FolderEntry {
+                        relative: entry.relative,
+                        raw: save.raw.clone(),
+                        save: Some(save),
+                    })
+                }
+                None => Ok(entry),
+            })
+            .collect::<Result<Vec<FolderEntry>, FESTError>>()?;
+
+        Ok(SaveFolder { entries })
+    }
+}
+
+fn list_files(
+    root: &Path,
+    dir: &Path,
+    files: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<(), FESTError> {
+    let mut entries = fs::read_dir(dir)
+        .map_err(|e| FESTError::ReadError(format!("'{}': {}", dir.display(), e)))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect::<Vec<PathBuf>>();
+
+    entries.sort();
+
+    for path in entries {
+        // NOTE: Linked directories are never followed, a link back up the tree would otherwise be listed forever
+        if fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+            && path.is_dir()
+        {
+            continue;
+        }
+
+        if path.is_dir() {
+            list_files(root, &path, files)?;
+        } else if path.is_file() {
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            files.push((path, relative));
+        }
+    }
+
+    Ok(())
+}
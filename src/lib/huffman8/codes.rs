@@ -1,5 +1,6 @@
-use crate::constants::{FESTError, HUF_LCHAR, HUF_NEXT, HUF_RCHAR};
+use crate::constants::{FESTError, HUF_LCHAR, HUF_LNODE, HUF_NEXT, HUF_RCHAR, HUF_RNODE};
 use crate::huffman8::Node;
+use log::trace;
 use std::fmt::Debug;
 use std::slice::SliceIndex;
 use std::{cell::RefCell, rc::Rc};
@@ -11,7 +12,7 @@ pub struct Codes {
 
 impl Codes {
     pub fn create_code(num_leafs: usize) -> Result<Self, FESTError> {
-        println!("CREATE_CODE");
+        trace!("Creating code tree for {} leafs", num_leafs);
         let max_nodes = ((((num_leafs) - 1) | 1) + 1) * 2;
 
         let mut code_tree = vec![0u8; max_nodes];
@@ -29,6 +30,39 @@ impl Codes {
         })
     }
 
+    // NOTE: Walks an already serialized "code_tree" the same way decompression does, giving the same "(symbol, scode)" pairs "Node::get_scode" would, so it can be fed to "Work::create_code_works"
+    pub fn read_code_tree(code_tree: &[u8]) -> Result<Vec<(usize, Vec<u8>)>, FESTError> {
+        let mut tree_data = vec![];
+        let mut stack: Vec<(usize, Vec<u8>)> = vec![(1, vec![])];
+
+        while let Some((index, scode)) = stack.pop() {
+            let node = *code_tree
+                .get(index)
+                .ok_or(FESTError::CodeTreeIOOB(index.to_string(), code_tree.len()))?;
+            let next = (index & !1) + ((((node & HUF_NEXT) as usize) + 1) << 1); // NOTE: Always moves forward, so a malformed tree ends up out of bounds instead of looping forever
+
+            for (son, mask, bit) in [
+                (next, HUF_LCHAR, HUF_LNODE),
+                (next + 1, HUF_RCHAR, HUF_RNODE),
+            ] {
+                let mut son_scode = vec![bit];
+                son_scode.extend(&scode);
+
+                if (node & mask) != 0 {
+                    let symbol = *code_tree
+                        .get(son)
+                        .ok_or(FESTError::CodeTreeIOOB(son.to_string(), code_tree.len()))?;
+
+                    tree_data.push((symbol as usize, son_scode));
+                } else {
+                    stack.push((son, son_scode));
+                }
+            }
+        }
+
+        Ok(tree_data)
+    }
+
     fn get_code_tree(&self, index: usize) -> Result<&u8, FESTError> {
         let code_tree_length = self.code_tree.len();
         self.code_tree
@@ -161,7 +195,7 @@ impl Codes {
     }
 
     pub fn update_code(&mut self) -> Result<(), FESTError> {
-        println!("UPDATE_CODE");
+        trace!("Updating code tree");
         let max_code_index = ((*self.get_code_tree(0)? as usize) + 1) << 1;
 
         let mut code_index = 1;
@@ -1,4 +1,5 @@
 use crate::constants::FESTError;
+use log::trace;
 
 pub struct Freqs {
     pub freqs: Vec<usize>,
@@ -7,7 +8,7 @@ pub struct Freqs {
 
 impl Freqs {
     pub fn create_freqs(data: &[u8]) -> Result<Self, FESTError> {
-        println!("CREATE_FREQS");
+        trace!("Counting frequencies of {} bytes", data.len());
 
         let mut freqs = vec![0usize; 0xFF + 1];
 
@@ -1,4 +1,5 @@
 use crate::constants::{FESTError, HUF_LNODE, HUF_RNODE};
+use log::trace;
 use std::{cell::RefCell, rc::Rc};
 
 #[derive(Debug, PartialEq, Eq)]
@@ -44,7 +45,7 @@ impl Node {
         freqs: &[usize],
         num_leafs: usize,
     ) -> Result<Vec<Rc<RefCell<Node>>>, FESTError> {
-        println!("CREATE_TREE");
+        trace!("Creating tree for {} leafs", num_leafs);
         // NOTE: When creating the tree, this means that "tree.len()" on first initialization is exactly the same as "num_leafs", so this may be removable
         let mut tree: Vec<Rc<RefCell<Node>>> = freqs
             .iter()
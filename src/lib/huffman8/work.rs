@@ -1,5 +1,6 @@
 use crate::constants::{FESTError, CMD_CODE, HUF_MASK, HUF_MASK4, HUF_SHIFT};
 use crate::utils::{from_uint32, to_uint32};
+use log::trace;
 use std::collections::HashMap;
 
 #[derive(Debug)]
@@ -9,7 +10,7 @@ pub struct Work {
 
 impl Work {
     pub fn create_code_works(tree_data: Vec<(usize, Vec<u8>)>) -> Result<Work, FESTError> {
-        println!("CREATE_CODE_WORKS");
+        trace!("Creating code works for {} symbols", tree_data.len());
         // NOTE: While doing some testing, the behavior of "codes" (Renamed to "works" here) is basically a dictionary, as the indices are the symbols (AKA indexes from "freqs") and the value is the "code" struct (Renamed to "work" here)
         let mut works: HashMap<usize, (usize, Vec<u8>)> = HashMap::with_capacity(tree_data.len());
 
@@ -44,7 +45,7 @@ impl Work {
     }
 
     pub fn process_data(self, data: &[u8], code_tree: &[u8]) -> Result<Vec<u8>, FESTError> {
-        println!("PROCESS_DATA");
+        trace!("Encoding {} bytes", data.len());
         // NOTE: These operations done to "pbuf" are actually be appends when considering only the written data
         let mut pbuf: Vec<u8> = vec![]; // NOTE: Got rid of "pak_pos", for similar reasons to "num_nodes" but now for pbuf
         pbuf.extend(from_uint32(CMD_CODE | ((data.len() as u32) << 8)));
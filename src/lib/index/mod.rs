@@ -0,0 +1,398 @@
+use crate::constants::{FESTError, INDE_ENTRIES_OFS, INDE_ENTRY_SIZE, INDE_MAGIC};
+use crate::layout::{
+    read_fields, Section, SectionKind, ENTRY_FIELDS, INDE_COUNT, INDE_FIELDS, INDE_MAGIC_FIELD,
+};
+use crate::utils::from_uint32;
+
+// Layout of the index, all values are little endian and offsets are relative to the magic (Fields are defined in "layout"):
+// 0x00: "INDE" magic
+// 0x04: Number of entries
+// 0x08: Entries of { id, offset, length }, one after the other
+// After the entries come the block bodies, which may have padding between them
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveBlock {
+    pub id: u32,
+    pub offset: u32,
+    pub data: Vec<u8>,
+    padding: Vec<u8>,
+}
+
+impl SaveBlock {
+    pub fn name(&self) -> Option<String> {
+        block_name(self.id)
+    }
+}
+
+// NOTE: IDs made of four letters or digits are names written backwards, like "TINU" for "UNIT"
+pub fn block_name(id: u32) -> Option<String> {
+    let bytes = from_uint32(id);
+
+    match bytes.iter().all(|byte| byte.is_ascii_alphanumeric()) {
+        true => Some(bytes.iter().rev().map(|&byte| byte as char).collect()),
+        false => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveIndex {
+    pub blocks: Vec<SaveBlock>,
+    gap: Vec<u8>,
+}
+
+impl SaveIndex {
+    pub fn parse(data: &[u8]) -> Result<Self, FESTError> {
+        Self::parse_layout(data, 0).map(|(index, _)| index)
+    }
+
+    // NOTE: The sections are what was read while parsing, moved by "base" so they can be placed in the whole data. Entries come in their order and blocks in the order they are stored, which isn't always the same
+    pub(crate) fn parse_layout(
+        data: &[u8],
+        base: usize,
+    ) -> Result<(Self, Vec<Section>), FESTError> {
+        if INDE_MAGIC_FIELD.read(data, 0)? != INDE_MAGIC {
+            return Err(FESTError::InvalidData);
+        }
+
+        let count = INDE_COUNT.read(data, 0)? as usize;
+        let table_end = count
+            .checked_mul(INDE_ENTRY_SIZE)
+            .and_then(|size| size.checked_add(INDE_ENTRIES_OFS))
+            .filter(|&end| end <= data.len())
+            .ok_or(FESTError::SaveIndexIOOB(INDE_ENTRIES_OFS, data.len()))?;
+
+        let mut sections = vec![Section {
+            kind: SectionKind::IndexHeader,
+            offset: 0,
+            len: INDE_ENTRIES_OFS,
+            fields: read_fields(data, 0, &INDE_FIELDS)?,
+        }];
+
+        let mut entries = vec![];
+        for entry in 0..count {
+            let entry_ofs = INDE_ENTRIES_OFS + entry * INDE_ENTRY_SIZE;
+            let fields = read_fields(data, entry_ofs, &ENTRY_FIELDS)?;
+
+            entries.push((
+                fields[0].value,
+                fields[1].value as usize,
+                fields[2].value as usize,
+            ));
+            sections.push(Section {
+                kind: SectionKind::IndexEntry(fields[0].value),
+                offset: entry_ofs,
+                len: INDE_ENTRY_SIZE,
+                fields,
+            });
+        }
+
+        // NOTE: Blocks are not guaranteed to be stored in the same order as their entries, so the gaps are computed following their offsets
+        let mut layout = (0..count).collect::<Vec<usize>>();
+        layout.sort_by_key(|&entry| entries[entry].1);
+
+        let (first_id, gap_end) = layout
+            .first()
+            .map(|&entry| (entries[entry].0, entries[entry].1))
+            .unwrap_or((0, data.len()));
+        let gap = data
+            .get(table_end..gap_end)
+            .ok_or(FESTError::BlockOverlap(first_id))?
+            .to_vec();
+
+        if !gap.is_empty() {
+            sections.push(Section {
+                kind: SectionKind::IndexGap,
+                offset: table_end,
+                len: gap.len(),
+                fields: vec![],
+            });
+        }
+
+        let mut blocks = vec![];
+        for (position, &entry) in layout.iter().enumerate() {
+            let (id, offset, length) = entries[entry];
+            let end = offset
+                .checked_add(length)
+                .ok_or(FESTError::SaveIndexIOOB(offset, data.len()))?;
+            let next = layout
+                .get(position + 1)
+                .map(|&next| entries[next].1)
+                .unwrap_or(data.len());
+
+            let block_data = data
+                .get(offset..end)
+                .ok_or(FESTError::SaveIndexIOOB(end, data.len()))?;
+            let padding = data.get(end..next).ok_or(FESTError::BlockOverlap(id))?;
+
+            sections.push(Section {
+                kind: SectionKind::Block(id),
+                offset,
+                len: length,
+                fields: vec![],
+            });
+            if !padding.is_empty() {
+                sections.push(Section {
+                    kind: SectionKind::Padding(id),
+                    offset: end,
+                    len: padding.len(),
+                    fields: vec![],
+                });
+            }
+
+            blocks.push((
+                entry,
+                SaveBlock {
+                    id,
+                    offset: offset as u32,
+                    data: block_data.to_vec(),
+                    padding: padding.to_vec(),
+                },
+            ));
+        }
+
+        blocks.sort_by_key(|&(entry, _)| entry);
+
+        let index = SaveIndex {
+            blocks: blocks.into_iter().map(|(_, block)| block).collect(),
+            gap,
+        };
+
+        for section in &mut sections {
+            section.move_by(base);
+        }
+
+        Ok((index, sections))
+    }
+
+    pub fn block(&self, id: u32) -> Option<&[u8]> {
+        self.blocks
+            .iter()
+            .find(|block| block.id == id)
+            .map(|block| block.data.as_slice())
+    }
+
+    pub fn replace(&mut self, id: u32, data: Vec<u8>) -> Result<Vec<u8>, FESTError> {
+        let block = self
+            .blocks
+            .iter_mut()
+            .find(|block| block.id == id)
+            .ok_or(FESTError::BlockNotFound(id))?;
+
+        let old_data = std::mem::replace(&mut block.data, data);
+        self.update_offsets();
+
+        Ok(old_data)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut index = self.clone();
+        index.update_offsets();
+
+        let mut raw = vec![];
+        raw.extend(from_uint32(INDE_MAGIC));
+        raw.extend(from_uint32(index.blocks.len() as u32));
+
+        for block in &index.blocks {
+            raw.extend(from_uint32(block.id));
+            raw.extend(from_uint32(block.offset));
+            raw.extend(from_uint32(block.data.len() as u32));
+        }
+
+        raw.extend(&index.gap);
+
+        for block in index.layout() {
+            raw.extend(&block.data);
+            raw.extend(&block.padding);
+        }
+
+        raw
+    }
+
+    fn layout(&self) -> Vec<&SaveBlock> {
+        let mut layout = self.blocks.iter().collect::<Vec<_>>();
+        layout.sort_by_key(|block| block.offset);
+
+        layout
+    }
+
+    fn update_offsets(&mut self) {
+        let mut layout = (0..self.blocks.len()).collect::<Vec<usize>>();
+        layout.sort_by_key(|&block| self.blocks[block].offset);
+
+        let mut offset = INDE_ENTRIES_OFS + self.blocks.len() * INDE_ENTRY_SIZE + self.gap.len();
+        for block in layout {
+            let block = &mut self.blocks[block];
+
+            block.offset = offset as u32;
+            offset += block.data.len() + block.padding.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNIT: u32 = 0x554E4954;
+    const CONV: u32 = 0x434F4E56;
+
+    // NOTE: Entries are { id, offset, length }, and everything after the table up to "len" is filled with its own offset
+    fn raw_index(entries: &[(u32, usize, usize)], len: usize) -> Vec<u8> {
+        let mut raw = vec![];
+        raw.extend(INDE_MAGIC.to_le_bytes());
+        raw.extend((entries.len() as u32).to_le_bytes());
+
+        for &(id, offset, length) in entries {
+            raw.extend(id.to_le_bytes());
+            raw.extend((offset as u32).to_le_bytes());
+            raw.extend((length as u32).to_le_bytes());
+        }
+
+        let table_end = raw.len();
+        raw.extend((table_end..len).map(|offset| offset as u8));
+
+        raw
+    }
+
+    #[test]
+    fn parses_blocks() {
+        let raw = raw_index(&[(UNIT, 32, 4), (CONV, 36, 4)], 40);
+        let index = SaveIndex::parse(&raw).unwrap();
+
+        assert_eq!(
+            index
+                .blocks
+                .iter()
+                .map(|block| (block.id, block.offset))
+                .collect::<Vec<(u32, u32)>>(),
+            [(UNIT, 32), (CONV, 36)]
+        );
+        assert_eq!(index.block(UNIT), Some(&[32, 33, 34, 35][..]));
+        assert_eq!(index.block(CONV), Some(&[36, 37, 38, 39][..]));
+        assert_eq!(index.block(0), None);
+        assert_eq!(index.blocks[0].name().as_deref(), Some("UNIT"));
+        assert_eq!(index.to_bytes(), raw);
+    }
+
+    #[test]
+    fn keeps_gap_and_padding() {
+        let raw = raw_index(&[(UNIT, 36, 4), (CONV, 44, 2)], 48);
+        let index = SaveIndex::parse(&raw).unwrap();
+
+        assert_eq!(index.gap, [32, 33, 34, 35]);
+        assert_eq!(index.blocks[0].padding, [40, 41, 42, 43]);
+        assert_eq!(index.blocks[1].padding, [46, 47]);
+        assert_eq!(index.to_bytes(), raw);
+    }
+
+    #[test]
+    fn keeps_blocks_out_of_order() {
+        // NOTE: The first entry is stored last
+        let raw = raw_index(&[(UNIT, 40, 4), (CONV, 32, 6)], 44);
+        let index = SaveIndex::parse(&raw).unwrap();
+
+        assert_eq!(index.blocks[0].id, UNIT);
+        assert_eq!(index.blocks[1].id, CONV);
+        assert_eq!(index.blocks[1].padding, [38, 39]);
+        assert_eq!(index.block(UNIT), Some(&[40, 41, 42, 43][..]));
+        assert_eq!(index.to_bytes(), raw);
+    }
+
+    #[test]
+    fn replace_moves_later_blocks() {
+        let raw = raw_index(&[(UNIT, 40, 4), (CONV, 32, 6)], 44);
+        let mut index = SaveIndex::parse(&raw).unwrap();
+
+        let old_data = index.replace(CONV, vec![0xFF; 10]).unwrap();
+        assert_eq!(old_data, [32, 33, 34, 35, 36, 37]);
+        assert_eq!(index.blocks[1].offset, 32);
+        assert_eq!(index.blocks[0].offset, 44); // NOTE: 4 bytes later, after the padding that followed the block that grew
+
+        let raw = index.to_bytes();
+        assert_eq!(raw.len(), 48);
+
+        let index = SaveIndex::parse(&raw).unwrap();
+        assert_eq!(index.block(CONV), Some(&[0xFF; 10][..]));
+        assert_eq!(index.block(UNIT), Some(&[40, 41, 42, 43][..]));
+        assert_eq!(index.blocks[1].padding, [38, 39]);
+    }
+
+    #[test]
+    fn replace_needs_the_block() {
+        let mut index = SaveIndex::parse(&raw_index(&[(UNIT, 20, 4)], 24)).unwrap();
+
+        assert!(matches!(
+            index.replace(CONV, vec![]),
+            Err(FESTError::BlockNotFound(CONV))
+        ));
+    }
+
+    #[test]
+    fn rejects_overlapping_blocks() {
+        let raw = raw_index(&[(UNIT, 32, 8), (CONV, 36, 4)], 40);
+        assert!(matches!(
+            SaveIndex::parse(&raw),
+            Err(FESTError::BlockOverlap(UNIT))
+        ));
+
+        // NOTE: A block starting inside the table overlaps it
+        let raw = raw_index(&[(UNIT, 16, 4)], 24);
+        assert!(matches!(
+            SaveIndex::parse(&raw),
+            Err(FESTError::BlockOverlap(UNIT))
+        ));
+    }
+
+    #[test]
+    fn rejects_out_of_bounds() {
+        let raw = raw_index(&[(UNIT, 20, 8)], 24);
+        assert!(matches!(
+            SaveIndex::parse(&raw),
+            Err(FESTError::SaveIndexIOOB(28, 24))
+        ));
+
+        // NOTE: More entries than there is room for
+        let mut raw = raw_index(&[(UNIT, 20, 4)], 24);
+        raw[4..8].copy_from_slice(&100u32.to_le_bytes());
+        assert!(matches!(
+            SaveIndex::parse(&raw),
+            Err(FESTError::SaveIndexIOOB(..))
+        ));
+    }
+
+    #[test]
+    fn rejects_other_magic() {
+        let mut raw = raw_index(&[], 8);
+        raw[0] = b'X';
+
+        assert!(matches!(
+            SaveIndex::parse(&raw),
+            Err(FESTError::InvalidData)
+        ));
+    }
+
+    #[test]
+    fn sections_follow_the_parse() {
+        let raw = raw_index(&[(UNIT, 40, 4), (CONV, 36, 2)], 46);
+        let (_, sections) = SaveIndex::parse_layout(&raw, 0x100).unwrap();
+
+        assert_eq!(
+            sections
+                .iter()
+                .map(|section| (section.kind, section.offset, section.len))
+                .collect::<Vec<(SectionKind, usize, usize)>>(),
+            [
+                (SectionKind::IndexHeader, 0x100, 8),
+                (SectionKind::IndexEntry(UNIT), 0x108, 12),
+                (SectionKind::IndexEntry(CONV), 0x114, 12),
+                (SectionKind::IndexGap, 0x120, 4),
+                (SectionKind::Block(CONV), 0x124, 2),
+                (SectionKind::Padding(CONV), 0x126, 2),
+                (SectionKind::Block(UNIT), 0x128, 4),
+                (SectionKind::Padding(UNIT), 0x12C, 2),
+            ]
+        );
+        assert_eq!(sections[1].fields[1].offset, 0x10C);
+        assert_eq!(sections[1].fields[1].value, 40);
+    }
+}
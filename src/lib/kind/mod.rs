@@ -0,0 +1,182 @@
+use crate::constants::{FESTError, CHAPTER_HEADER_LEN};
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SaveKind {
+    Global,
+    Chapter,
+    Map,
+    Backup,
+}
+
+impl SaveKind {
+    pub const ALL: [SaveKind; 4] = [
+        SaveKind::Global,
+        SaveKind::Chapter,
+        SaveKind::Map,
+        SaveKind::Backup,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SaveKind::Global => "Global",
+            SaveKind::Chapter => "Chapter",
+            SaveKind::Map => "Map",
+            SaveKind::Backup => "Backup",
+        }
+    }
+
+    // NOTE: Offsets where the "COMP"/"INDE" magic is expected, in the order they should be probed
+    pub fn header_lens(&self) -> &'static [usize] {
+        match self {
+            SaveKind::Global => &[0],
+            SaveKind::Chapter => &[CHAPTER_HEADER_LEN],
+            SaveKind::Map | SaveKind::Backup => &[CHAPTER_HEADER_LEN, 0],
+        }
+    }
+
+    // NOTE: Map and Backup saves can only be told apart by their file names, as no block only they have is known
+    pub fn from_header_len(header_len: usize) -> Self {
+        match header_len {
+            CHAPTER_HEADER_LEN => SaveKind::Chapter,
+            _ => SaveKind::Global,
+        }
+    }
+
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let name = base_name(file_name).to_ascii_lowercase();
+
+        if name.starts_with("backup") || name.ends_with(".bak") {
+            return Some(SaveKind::Backup);
+        }
+
+        if name.starts_with("global") {
+            Some(SaveKind::Global)
+        } else if name.starts_with("chapter") {
+            Some(SaveKind::Chapter)
+        } else if name.starts_with("map") || name.starts_with("suspend") {
+            Some(SaveKind::Map)
+        } else {
+            None
+        }
+    }
+
+    pub fn slot_from_file_name(file_name: &str) -> Option<u8> {
+        let name = base_name(file_name)
+            .trim_end_matches("_dec")
+            .trim_end_matches("_com");
+        let name = name.split('.').next().unwrap_or(name);
+
+        let prefix = name.trim_end_matches(|c: char| c.is_ascii_digit());
+
+        name[prefix.len()..].parse().ok()
+    }
+}
+
+fn base_name(file_name: &str) -> &str {
+    file_name.rsplit(['/', '\\']).next().unwrap_or(file_name)
+}
+
+impl fmt::Display for SaveKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for SaveKind {
+    type Err = FESTError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        SaveKind::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(value))
+            .ok_or(FESTError::UnknownKind(value.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FESData;
+
+    fn index_save(header_len: usize, block_ids: &[u32]) -> Vec<u8> {
+        let mut raw = vec![0; header_len];
+        raw.extend(0x494E4445u32.to_le_bytes());
+        raw.extend((block_ids.len() as u32).to_le_bytes());
+
+        for &id in block_ids {
+            raw.extend(id.to_le_bytes());
+            raw.extend(((8 + block_ids.len() * 12) as u32).to_le_bytes());
+            raw.extend(0u32.to_le_bytes());
+        }
+
+        raw
+    }
+
+    #[test]
+    fn kind_from_file_name() {
+        let cases = [
+            ("Global", Some(SaveKind::Global)),
+            ("global_dec", Some(SaveKind::Global)),
+            ("Chapter0", Some(SaveKind::Chapter)),
+            ("saves/Chapter2_com", Some(SaveKind::Chapter)),
+            ("Map1", Some(SaveKind::Map)),
+            ("C:\\saves\\Suspend0", Some(SaveKind::Map)),
+            ("Backup1", Some(SaveKind::Backup)),
+            ("Chapter1.bak", Some(SaveKind::Backup)),
+            ("notes.txt", None),
+            ("", None),
+        ];
+
+        for (file_name, kind) in cases {
+            assert_eq!(SaveKind::from_file_name(file_name), kind, "{}", file_name);
+        }
+    }
+
+    #[test]
+    fn slot_from_file_name() {
+        let cases = [
+            ("Chapter0", Some(0)),
+            ("Chapter2_dec", Some(2)),
+            ("dir/Map13_com", Some(13)),
+            ("Chapter1.bak", Some(1)),
+            ("Global", None),
+            ("Chapter", None),
+            ("Chapter999", None), // NOTE: Slots are a "u8"
+        ];
+
+        for (file_name, slot) in cases {
+            assert_eq!(
+                SaveKind::slot_from_file_name(file_name),
+                slot,
+                "{}",
+                file_name
+            );
+        }
+    }
+
+    #[test]
+    fn kind_from_header() {
+        let kind = |raw: &[u8]| FESData::process_data(raw).unwrap().kind();
+
+        assert_eq!(kind(&index_save(0, &[1])), SaveKind::Global);
+        assert_eq!(
+            kind(&index_save(CHAPTER_HEADER_LEN, &[1])),
+            SaveKind::Chapter
+        );
+    }
+
+    #[test]
+    fn file_name_beats_header() {
+        let raw = index_save(CHAPTER_HEADER_LEN, &[1]);
+
+        assert_eq!(
+            FESData::process_file_data(&raw, "Suspend0").unwrap().kind(),
+            SaveKind::Map
+        );
+        assert_eq!(
+            FESData::process_file_data(&raw, "Global").unwrap().kind(),
+            SaveKind::Chapter
+        ); // NOTE: Global saves have no chapter header, so the name is given up on
+    }
+}
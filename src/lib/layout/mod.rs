@@ -0,0 +1,186 @@
+use crate::constants::{FESTError, COMP_HEADER_LEN, HUF_TREEOFS};
+use crate::index::block_name;
+use crate::utils::to_uint32;
+
+// NOTE: Every field the parsers read is defined here and only here, so anything describing the data (Like "fest hexdump") can't drift from how it is actually read. All fields are little endian u32s, with offsets relative to the start of their structure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    pub name: &'static str,
+    pub offset: usize,
+}
+
+impl Field {
+    pub const LEN: usize = 4;
+
+    pub fn read(&self, data: &[u8], base: usize) -> Result<u32, FESTError> {
+        to_uint32(data, base + self.offset)
+    }
+}
+
+pub const COMP_MAGIC_FIELD: Field = Field {
+    name: "magic",
+    offset: 0x0,
+};
+pub const COMP_VERSION: Field = Field {
+    name: "version",
+    offset: 0x4,
+};
+pub const COMP_LENGTH: Field = Field {
+    name: "length",
+    offset: 0x8,
+};
+pub const COMP_CHECKSUM: Field = Field {
+    name: "checksum",
+    offset: 0xC,
+};
+pub const COMP_FIELDS: [Field; 4] = [COMP_MAGIC_FIELD, COMP_VERSION, COMP_LENGTH, COMP_CHECKSUM];
+
+// NOTE: Bits 0-3 are the bits per symbol and bits 8-31 the decompressed length, the code tree follows right after it
+pub const HUF_HEADER: Field = Field {
+    name: "huffman header",
+    offset: 0x0,
+};
+
+pub const INDE_MAGIC_FIELD: Field = Field {
+    name: "magic",
+    offset: 0x0,
+};
+pub const INDE_COUNT: Field = Field {
+    name: "count",
+    offset: 0x4,
+};
+pub const INDE_FIELDS: [Field; 2] = [INDE_MAGIC_FIELD, INDE_COUNT];
+
+pub const ENTRY_ID: Field = Field {
+    name: "id",
+    offset: 0x0,
+};
+pub const ENTRY_OFFSET: Field = Field {
+    name: "offset",
+    offset: 0x4,
+};
+pub const ENTRY_LENGTH: Field = Field {
+    name: "length",
+    offset: 0x8,
+};
+pub const ENTRY_FIELDS: [Field; 3] = [ENTRY_ID, ENTRY_OFFSET, ENTRY_LENGTH];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionKind {
+    ChapterHeader,
+    CompHeader,
+    HuffmanHeader,
+    CodeTree,
+    CompressedData,
+    IndexHeader,
+    IndexEntry(u32),
+    IndexGap,
+    Block(u32),
+    Padding(u32), // NOTE: After the block with this ID
+}
+
+impl SectionKind {
+    pub fn name(&self) -> String {
+        let block = |id: u32| match block_name(id) {
+            Some(name) => format!("{:#010X} '{}'", id, name),
+            None => format!("{:#010X}", id),
+        };
+
+        match self {
+            SectionKind::ChapterHeader => "Chapter header".to_string(),
+            SectionKind::CompHeader => "COMP header".to_string(),
+            SectionKind::HuffmanHeader => "Huffman header".to_string(),
+            SectionKind::CodeTree => "Huffman code tree".to_string(),
+            SectionKind::CompressedData => "Compressed data".to_string(),
+            SectionKind::IndexHeader => "INDE header".to_string(),
+            SectionKind::IndexEntry(id) => format!("INDE entry of block {}", block(*id)),
+            SectionKind::IndexGap => "Gap after the INDE entries".to_string(),
+            SectionKind::Block(id) => format!("Block {}", block(*id)),
+            SectionKind::Padding(id) => format!("Padding after block {}", block(*id)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldValue {
+    pub field: Field,
+    pub offset: usize, // NOTE: Where the field is in the whole data
+    pub value: u32,
+}
+
+// NOTE: "offset" is where the section starts in the whole data, including the chapter header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub kind: SectionKind,
+    pub offset: usize,
+    pub len: usize,
+    pub fields: Vec<FieldValue>,
+}
+
+impl Section {
+    pub(crate) fn move_by(&mut self, by: usize) {
+        self.offset += by;
+        for field in &mut self.fields {
+            field.offset += by;
+        }
+    }
+}
+
+pub(crate) fn read_fields(
+    data: &[u8],
+    base: usize,
+    fields: &[Field],
+) -> Result<Vec<FieldValue>, FESTError> {
+    fields
+        .iter()
+        .map(|&field| {
+            Ok(FieldValue {
+                field,
+                offset: base + field.offset,
+                value: field.read(data, base)?,
+            })
+        })
+        .collect()
+}
+
+// NOTE: "data" is the whole data, and "start" where the COMP header is
+pub fn comp_sections(
+    data: &[u8],
+    start: usize,
+    tree_end: usize,
+) -> Result<Vec<Section>, FESTError> {
+    let huffman = start + COMP_HEADER_LEN;
+    let tree = huffman + HUF_TREEOFS;
+    let compressed = huffman + tree_end;
+
+    if compressed > data.len() {
+        return Err(FESTError::DecompressDataIOOB(compressed, data.len()));
+    }
+
+    Ok(vec![
+        Section {
+            kind: SectionKind::CompHeader,
+            offset: start,
+            len: COMP_HEADER_LEN,
+            fields: read_fields(data, start, &COMP_FIELDS)?,
+        },
+        Section {
+            kind: SectionKind::HuffmanHeader,
+            offset: huffman,
+            len: Field::LEN,
+            fields: read_fields(data, huffman, &[HUF_HEADER])?,
+        },
+        Section {
+            kind: SectionKind::CodeTree,
+            offset: tree,
+            len: compressed - tree,
+            fields: vec![],
+        },
+        Section {
+            kind: SectionKind::CompressedData,
+            offset: compressed,
+            len: data.len() - compressed,
+            fields: vec![],
+        },
+    ])
+}
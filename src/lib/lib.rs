@@ -1,51 +1,130 @@
+mod archive;
+mod backup;
 mod checksum;
+mod comp;
 mod constants;
+mod diff;
+mod folder;
 mod huffman8;
+mod index;
+mod kind;
+mod layout;
+mod locate;
 mod utils;
+mod variant;
+mod write;
+mod zipped;
 
+pub use archive::{ArchiveEntry, SaveArchive};
+pub use backup::{backup_file, list_backups};
 use checksum::get_checksum;
+use comp::CompEncoding;
+pub use comp::{CompHeader, TreeInfo};
+pub use constants::FESTError;
 use constants::{
-    FESTError, COMP_MAGIC, HUF_LCHAR, HUF_MASK4, HUF_NEXT, HUF_RCHAR, HUF_SHIFT, HUF_TREEOFS,
-    INDE_MAGIC,
+    CHAPTER_HEADER_LEN, COMP_HEADER_LEN, COMP_MAGIC, HUF_LCHAR, HUF_MASK4, HUF_NEXT, HUF_RCHAR,
+    HUF_SHIFT, HUF_TREEOFS, INDE_MAGIC, SCAN_ALIGNMENT, SCAN_LIMIT,
 };
+pub use diff::{diff_saves, ByteRange, RegionDiff};
+pub use folder::{FolderEntry, SaveFolder};
 use huffman8::{Codes, Freqs, Node, Work};
-use std::{fs::File, io::Write};
-use utils::{from_uint32, to_uint32};
+pub use index::{SaveBlock, SaveIndex};
+pub use kind::SaveKind;
+pub use layout::{Field, FieldValue, Section, SectionKind};
+pub use locate::{default_roots, locate_saves, LocatedSave, Title, TITLES};
+use log::debug;
+use std::{
+    io::{Read, Write},
+    path::Path,
+    time::Instant,
+};
+use utils::to_uint32;
+pub use variant::GameVariant;
+pub use write::{write_atomic, WriteOptions};
+pub use zipped::{read_zip, rewrite_zip, ZippedSave};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FESData {
     pub raw: Vec<u8>,
     pub is_compressed: bool,
-    is_chapter: bool,
+    header_len: usize,
+    kind: SaveKind,
+    variant: Option<GameVariant>,
+    comp_header: Option<CompHeader>,
+    encoding: Option<CompEncoding>,
 }
 
 impl FESData {
     pub fn process_data(raw: &[u8]) -> Result<FESData, FESTError> {
-        match to_uint32(raw, 0)? {
-            COMP_MAGIC => Ok(FESData {
-                raw: raw.to_vec(),
-                is_compressed: true,
-                is_chapter: false,
-            }),
-            INDE_MAGIC => Ok(FESData {
+        Self::probe_data(raw, &[0, CHAPTER_HEADER_LEN], None)
+    }
+
+    pub fn read_from<R: Read>(mut reader: R) -> Result<FESData, FESTError> {
+        let mut raw = vec![];
+        reader
+            .read_to_end(&mut raw)
+            .map_err(|e| FESTError::ReadError(e.to_string()))?;
+
+        Self::process_data(&raw)
+    }
+
+    pub fn process_data_as(raw: &[u8], kind: SaveKind) -> Result<FESData, FESTError> {
+        Self::probe_data(raw, kind.header_lens(), Some(kind))
+    }
+
+    // NOTE: For data whose header length is already known, like saves found with "scan_data" before
+    pub fn process_data_at(
+        raw: &[u8],
+        header_len: usize,
+        kind: SaveKind,
+    ) -> Result<FESData, FESTError> {
+        Self::probe_data(raw, &[header_len], Some(kind))
+    }
+
+    pub fn process_file_data(raw: &[u8], file_name: &str) -> Result<FESData, FESTError> {
+        match SaveKind::from_file_name(file_name) {
+            Some(kind) => Self::process_data_as(raw, kind).or_else(|_| Self::process_data(raw)),
+            None => Self::process_data(raw),
+        }
+    }
+
+    pub fn scan_data(raw: &[u8]) -> Result<FESData, FESTError> {
+        let limit = raw.len().min(SCAN_LIMIT);
+        let header_lens = (0..limit).step_by(SCAN_ALIGNMENT).collect::<Vec<usize>>();
+
+        Self::probe_data(raw, &header_lens, None).map_err(|_| FESTError::MagicNotFound(limit))
+    }
+
+    fn probe_data(
+        raw: &[u8],
+        header_lens: &[usize],
+        kind: Option<SaveKind>,
+    ) -> Result<FESData, FESTError> {
+        for &header_len in header_lens {
+            let is_compressed = match to_uint32(raw, header_len) {
+                Ok(COMP_MAGIC) => true,
+                Ok(INDE_MAGIC) => false,
+                _ => continue,
+            };
+
+            debug!(
+                "Found {} magic at {:#X}",
+                if is_compressed { "COMP" } else { "INDE" },
+                header_len
+            );
+
+            return Ok(FESData {
                 raw: raw.to_vec(),
-                is_compressed: false,
-                is_chapter: false,
-            }),
-            _ => match to_uint32(raw, 0xC0)? {
-                COMP_MAGIC => Ok(FESData {
-                    raw: raw.to_vec(),
-                    is_compressed: true,
-                    is_chapter: true,
-                }),
-                INDE_MAGIC => Ok(FESData {
-                    raw: raw.to_vec(),
-                    is_compressed: false,
-                    is_chapter: true,
-                }),
-                _ => Err(FESTError::InvalidData),
-            },
+                is_compressed,
+                header_len,
+                kind: kind.unwrap_or(SaveKind::from_header_len(header_len)),
+                variant: None,
+                comp_header: None,
+                encoding: None,
+            });
         }
+
+        Err(FESTError::InvalidData)
     }
 
     pub fn decompress(self) -> Result<Self, FESTError> {
@@ -53,25 +132,45 @@ impl FESData {
             return Ok(self);
         }
 
-        let header = if self.is_chapter {
-            self.raw.get(..0xC0).ok_or(FESTError::InvalidData)?.to_vec()
-        } else {
-            vec![]
-        };
+        let start = Instant::now();
 
-        let data = match self.is_chapter {
-            true => self.raw.get(0xD0..).ok_or(FESTError::InvalidData)?,
-            false => self.raw.get(0x10..).ok_or(FESTError::InvalidData)?,
-        };
+        let header = self.header()?.to_vec();
+        let comp_header = CompHeader::parse(self.body()?)?;
+
+        let data = self
+            .raw
+            .get((self.header_len + COMP_HEADER_LEN)..)
+            .ok_or(FESTError::InvalidData)?;
+
+        let (decompressed, pak_len) = decompress(data)?;
+        let code_tree = data
+            .get(HUF_TREEOFS..tree_end(data)?)
+            .ok_or(FESTError::DecompressDataIOOB(HUF_TREEOFS, data.len()))?;
+        let padding = data.get(pak_len..).unwrap_or_default();
 
         let mut raw = vec![];
         raw.extend(header);
-        raw.extend(decompress(data)?);
+        raw.extend(&decompressed);
+
+        debug!(
+            "Decompressed {} bytes into {} in {:?}",
+            self.raw.len(),
+            raw.len(),
+            start.elapsed()
+        );
 
         Ok(FESData {
             raw,
             is_compressed: false,
-            is_chapter: self.is_chapter,
+            header_len: self.header_len,
+            kind: self.kind,
+            variant: self.variant,
+            comp_header: Some(comp_header),
+            encoding: Some(CompEncoding {
+                code_tree: code_tree.to_vec(),
+                padding: padding.to_vec(),
+                data: decompressed,
+            }),
         })
     }
 
@@ -80,48 +179,234 @@ impl FESData {
             return Ok(self);
         }
 
-        let header = {
-            match self.is_chapter {
-                true => self.raw.get(..0xC0).ok_or(FESTError::InvalidData)?.to_vec(),
-                false => vec![],
-            }
+        let start = Instant::now();
+
+        let header = self.header()?.to_vec();
+        let data = self.body()?;
+
+        let checksum = self.checksum()?;
+
+        let comp_header = CompHeader {
+            version: self
+                .comp_header
+                .map(|comp_header| comp_header.version)
+                .unwrap_or(CompHeader::DEFAULT_VERSION),
+            length: data.len() as u32,
+            checksum,
         };
 
-        let data = {
-            match self.is_chapter {
-                true => self.raw.get(0xC0..).ok_or(FESTError::InvalidData)?,
-                false => &self.raw,
-            }
+        // NOTE: Reusing the original tree gives back the exact same bytes when the data wasn't touched, but it can only be done while it still has a code for every symbol in the data
+        let compressed = match &self.encoding {
+            Some(encoding) => match compress_with_tree(data, encoding) {
+                Err(FESTError::WorkNotFound(_)) => {
+                    debug!("Original tree can't encode the data anymore, building a new one");
+                    compress(data)?
+                }
+                compressed => compressed?,
+            },
+            None => compress(data)?,
         };
 
         let mut raw = vec![];
         raw.extend(&header);
-        raw.extend(from_uint32(COMP_MAGIC));
-        raw.extend(from_uint32(2));
-        raw.extend(from_uint32(data.len() as u32));
+        raw.extend(comp_header.to_bytes());
+        raw.extend(compressed);
 
-        let checksum = get_checksum(0, &header)?;
-        let checksum = get_checksum(checksum, data)?;
-
-        raw.extend(from_uint32(checksum));
-        raw.extend(compress(data)?);
+        debug!(
+            "Compressed {} bytes into {} in {:?}",
+            self.raw.len(),
+            raw.len(),
+            start.elapsed()
+        );
 
         Ok(FESData {
             raw,
             is_compressed: true,
-            is_chapter: self.is_chapter,
+            header_len: self.header_len,
+            kind: self.kind,
+            variant: self.variant,
+            comp_header: None,
+            encoding: None,
         })
     }
 
-    pub fn write_to(&self, file_name: &str) -> Result<(), FESTError> {
-        let mut file = File::create(file_name).map_err(|t| FESTError::WriteError(t.to_string()))?;
+    // NOTE: Compressed data is checked against the checksum and length in its COMP header, while decompressed data has to survive a compression round trip unchanged
+    pub fn verify(&self) -> Result<bool, FESTError> {
+        match self.is_compressed {
+            true => {
+                let comp_header = self.comp_header().ok_or(FESTError::InvalidData)?;
+                let decompressed = self.clone().decompress()?;
+
+                Ok(comp_header.checksum == decompressed.checksum()?
+                    && comp_header.length as usize == decompressed.body()?.len())
+            }
+            false => Ok(self.clone().compress()?.decompress()?.raw == self.raw),
+        }
+    }
+
+    // NOTE: The checksum a COMP header would get for this data, which covers the header before it too
+    pub fn checksum(&self) -> Result<u32, FESTError> {
+        if self.is_compressed {
+            return Err(FESTError::NotDecompressed);
+        }
+
+        let checksum = get_checksum(0, self.header()?)?;
+        get_checksum(checksum, self.body()?)
+    }
+
+    // NOTE: Decompressed data only knows its tree when it was decompressed here, otherwise there's none
+    pub fn tree_info(&self) -> Result<Option<TreeInfo>, FESTError> {
+        let code_tree = match (self.is_compressed, &self.encoding) {
+            (true, _) => {
+                let data = self
+                    .raw
+                    .get((self.header_len + COMP_HEADER_LEN)..)
+                    .ok_or(FESTError::InvalidData)?;
+
+                data.get(HUF_TREEOFS..tree_end(data)?)
+                    .ok_or(FESTError::DecompressDataIOOB(HUF_TREEOFS, data.len()))?
+            }
+            (false, Some(encoding)) => &encoding.code_tree,
+            (false, None) => return Ok(None),
+        };
+
+        Ok(Some(TreeInfo {
+            size: code_tree.len(),
+            symbols: Codes::read_code_tree(code_tree)?.len(),
+        }))
+    }
+
+    // NOTE: Every part of the data as it is now, in the order they are stored, with the fields the parsers read from each one
+    pub fn layout(&self) -> Result<Vec<Section>, FESTError> {
+        let mut sections = vec![];
+
+        if self.header_len > 0 {
+            sections.push(Section {
+                kind: SectionKind::ChapterHeader,
+                offset: 0,
+                len: self.header_len,
+                fields: vec![], // NOTE: None of its fields were checked against real saves yet
+            });
+        }
+
+        match self.is_compressed {
+            true => {
+                let data = self
+                    .raw
+                    .get((self.header_len + COMP_HEADER_LEN)..)
+                    .ok_or(FESTError::InvalidData)?;
+
+                sections.extend(layout::comp_sections(
+                    &self.raw,
+                    self.header_len,
+                    tree_end(data)?,
+                )?);
+            }
+            false => sections.extend(SaveIndex::parse_layout(self.body()?, self.header_len)?.1),
+        }
+
+        Ok(sections)
+    }
+
+    pub fn index(&self) -> Result<SaveIndex, FESTError> {
+        if self.is_compressed {
+            return Err(FESTError::NotDecompressed);
+        }
+
+        SaveIndex::parse(self.body()?)
+    }
+
+    pub fn set_index(&mut self, index: &SaveIndex) -> Result<(), FESTError> {
+        if self.is_compressed {
+            return Err(FESTError::NotDecompressed);
+        }
+
+        self.raw.truncate(self.header_len);
+        self.raw.extend(index.to_bytes());
+
+        Ok(())
+    }
+
+    pub fn kind(&self) -> SaveKind {
+        self.kind
+    }
+
+    pub fn set_kind(&mut self, kind: SaveKind) {
+        self.kind = kind;
+    }
+
+    pub fn variant(&self) -> Option<GameVariant> {
+        self.variant
+    }
+
+    pub fn set_variant(&mut self, variant: GameVariant) {
+        self.variant = Some(variant);
+    }
+
+    // NOTE: Only the version is kept for the next compression, the length and checksum are always computed again
+    pub fn set_comp_version(&mut self, version: u32) {
+        self.comp_header = Some(CompHeader {
+            version,
+            ..self.comp_header.unwrap_or(CompHeader {
+                version,
+                length: 0,
+                checksum: 0,
+            })
+        });
+    }
+
+    pub fn comp_header(&self) -> Option<CompHeader> {
+        match self.is_compressed {
+            true => CompHeader::parse(self.body().ok()?).ok(),
+            false => self.comp_header,
+        }
+    }
+
+    pub fn header_len(&self) -> usize {
+        self.header_len
+    }
+
+    pub fn header(&self) -> Result<&[u8], FESTError> {
+        self.raw
+            .get(..self.header_len)
+            .ok_or(FESTError::InvalidData)
+    }
+
+    fn body(&self) -> Result<&[u8], FESTError> {
+        self.raw
+            .get(self.header_len..)
+            .ok_or(FESTError::InvalidData)
+    }
+
+    pub fn write_into<W: Write>(&self, mut writer: W) -> Result<(), FESTError> {
+        writer
+            .write_all(&self.raw)
+            .and_then(|_| writer.flush())
+            .map_err(|e| FESTError::WriteError(e.to_string()))
+    }
+
+    pub fn write_to<P: AsRef<Path>>(&self, file_name: P) -> Result<(), FESTError> {
+        self.write_with(file_name, WriteOptions::default())
+    }
 
-        file.write_all(&self.raw)
-            .map_err(|t| FESTError::WriteError(t.to_string()))
+    pub fn write_with<P: AsRef<Path>>(
+        &self,
+        file_name: P,
+        options: WriteOptions,
+    ) -> Result<(), FESTError> {
+        write::write_atomic(file_name.as_ref(), &self.raw, options)
     }
 }
 
-fn decompress(data: &[u8]) -> Result<Vec<u8>, FESTError> {
+fn tree_end(data: &[u8]) -> Result<usize, FESTError> {
+    let value = *data
+        .get(HUF_TREEOFS)
+        .ok_or(FESTError::DecompressDataIOOB(HUF_TREEOFS, data.len()))? as usize;
+
+    Ok(HUF_TREEOFS + ((value + 1) << 1))
+}
+
+fn decompress(data: &[u8]) -> Result<(Vec<u8>, usize), FESTError> {
     let header = to_uint32(data, 0)?;
     let num_bits = (header & 0xF) as u8;
     let mut decompressed = vec![0u8; (header >> 8) as usize];
@@ -198,7 +483,7 @@ fn decompress(data: &[u8]) -> Result<Vec<u8>, FESTError> {
         }
     }
 
-    Ok(decompressed)
+    Ok((decompressed, pak_pos))
 }
 
 fn compress(data: &[u8]) -> Result<Vec<u8>, FESTError> {
@@ -229,3 +514,82 @@ fn compress(data: &[u8]) -> Result<Vec<u8>, FESTError> {
 
     work.process_data(data, &codes.code_tree)
 }
+
+fn compress_with_tree(data: &[u8], encoding: &CompEncoding) -> Result<Vec<u8>, FESTError> {
+    let tree_data = Codes::read_code_tree(&encoding.code_tree)?;
+
+    let mut compressed =
+        Work::create_code_works(tree_data)?.process_data(data, &encoding.code_tree)?;
+
+    // NOTE: Edited data ends where the encoder leaves it, like data compressed with a new tree
+    if data == encoding.data {
+        compressed.extend(&encoding.padding);
+    }
+
+    Ok(compressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PREAMBLE_LEN: usize = 0x40;
+
+    fn save_with_preamble() -> Vec<u8> {
+        let mut raw = (0..PREAMBLE_LEN)
+            .map(|byte| byte as u8)
+            .collect::<Vec<u8>>();
+        let data = b"Chrom Lissa Frederick".repeat(8);
+
+        raw.extend(INDE_MAGIC.to_le_bytes());
+        raw.extend(1u32.to_le_bytes());
+        raw.extend(0x554E4954u32.to_le_bytes());
+        raw.extend(20u32.to_le_bytes());
+        raw.extend((data.len() as u32).to_le_bytes());
+        raw.extend(data);
+
+        raw
+    }
+
+    #[test]
+    fn scanned_preamble_is_kept() {
+        let plain = save_with_preamble();
+
+        let decompressed = FESData::scan_data(&plain).unwrap();
+        assert_eq!(decompressed.header_len(), PREAMBLE_LEN);
+        assert!(!decompressed.is_compressed);
+
+        let compressed = decompressed.compress().unwrap();
+        assert_eq!(compressed.raw[..PREAMBLE_LEN], plain[..PREAMBLE_LEN]);
+
+        let scanned = FESData::scan_data(&compressed.raw).unwrap();
+        assert_eq!(scanned.header_len(), PREAMBLE_LEN);
+        assert!(scanned.is_compressed);
+
+        let decompressed = scanned.decompress().unwrap();
+        assert_eq!(decompressed.raw, plain);
+        assert_eq!(decompressed.compress().unwrap().raw, compressed.raw);
+    }
+
+    #[test]
+    fn scanning_stops_at_the_limit() {
+        assert!(matches!(
+            FESData::scan_data(&[0xFF; 0x20]),
+            Err(FESTError::MagicNotFound(0x20))
+        ));
+
+        let mut raw = vec![0xFF; SCAN_LIMIT];
+        raw.extend(INDE_MAGIC.to_le_bytes());
+        assert!(matches!(
+            FESData::scan_data(&raw),
+            Err(FESTError::MagicNotFound(SCAN_LIMIT))
+        ));
+
+        let mut raw = vec![0xFF; SCAN_LIMIT - SCAN_ALIGNMENT];
+        raw.extend(save_with_preamble()[PREAMBLE_LEN..].iter());
+        assert_eq!(
+            FESData::scan_data(&raw).unwrap().header_len(),
+            SCAN_LIMIT - SCAN_ALIGNMENT
+        );
+    }
+}
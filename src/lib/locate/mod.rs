@@ -0,0 +1,224 @@
+use crate::constants::FESTError;
+use crate::{FESData, GameVariant, SaveKind};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+// NOTE: Save data of a title lives in "sdmc/Nintendo 3DS/<ID0>/<ID1>/title/<high>/<low>/data", where "<high>" and "<low>" are the title ID split in halves and written in hex
+const TITLE_HIGH: &str = "00040000";
+const LOCATE_DEPTH: usize = 8; // NOTE: Deep enough to get from the emulator's directory to "<low>", which is 7 levels below it
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Title {
+    pub id: u64,
+    pub variant: GameVariant,
+    pub region: &'static str,
+}
+
+impl Title {
+    fn low(&self) -> String {
+        format!("{:08x}", self.id & 0xFFFFFFFF)
+    }
+}
+
+// NOTE: If a release isn't found, its title ID should be added here
+pub const TITLES: [Title; 12] = [
+    Title {
+        id: 0x00040000000A0400,
+        variant: GameVariant::Awakening,
+        region: "JPN",
+    },
+    Title {
+        id: 0x00040000000A0500,
+        variant: GameVariant::Awakening,
+        region: "USA",
+    },
+    Title {
+        id: 0x00040000000A0600,
+        variant: GameVariant::Awakening,
+        region: "EUR",
+    },
+    Title {
+        id: 0x0004000000120700,
+        variant: GameVariant::Fates,
+        region: "JPN",
+    },
+    Title {
+        id: 0x0004000000179400,
+        variant: GameVariant::Fates,
+        region: "USA",
+    },
+    Title {
+        id: 0x0004000000179500,
+        variant: GameVariant::Fates,
+        region: "USA",
+    },
+    Title {
+        id: 0x0004000000179600,
+        variant: GameVariant::Fates,
+        region: "USA",
+    },
+    Title {
+        id: 0x000400000017A400,
+        variant: GameVariant::Fates,
+        region: "EUR",
+    },
+    Title {
+        id: 0x000400000017A500,
+        variant: GameVariant::Fates,
+        region: "EUR",
+    },
+    Title {
+        id: 0x000400000016C800,
+        variant: GameVariant::Echoes,
+        region: "JPN",
+    },
+    Title {
+        id: 0x00040000001B4000,
+        variant: GameVariant::Echoes,
+        region: "USA",
+    },
+    Title {
+        id: 0x00040000001B4100,
+        variant: GameVariant::Echoes,
+        region: "EUR",
+    },
+];
+
+#[derive(Debug, Clone)]
+pub struct LocatedSave {
+    pub path: PathBuf,
+    pub title: Title,
+    pub kind: Option<SaveKind>, // NOTE: Only set for files that are actually saves
+    pub slot: Option<u8>,
+}
+
+// NOTE: Where Citra and the emulators forked from it keep their data, the ones that don't exist are left out
+pub fn default_roots() -> Vec<PathBuf> {
+    let mut roots = vec![];
+
+    if let Some(home) = env::var_os("HOME").map(PathBuf::from) {
+        let data = env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .unwrap_or(home.join(".local/share"));
+
+        roots.extend(["citra-emu", "lime3ds-emu", "azahar-emu"].map(|name| data.join(name)));
+        roots.extend(
+            [
+                "org.citra_emu.citra/data/citra-emu",
+                "io.github.lime3ds.Lime3DS/data/lime3ds-emu",
+                "org.azahar_emu.Azahar/data/azahar-emu",
+            ]
+            .map(|name| home.join(".var/app").join(name)),
+        );
+        roots.extend(
+            ["Citra", "Lime3DS", "Azahar"]
+                .map(|name| home.join("Library/Application Support").join(name)),
+        );
+    }
+
+    if let Some(app_data) = env::var_os("APPDATA").map(PathBuf::from) {
+        roots.extend(["Citra", "Lime3DS", "Azahar"].map(|name| app_data.join(name)));
+    }
+
+    roots.retain(|root| root.is_dir());
+
+    roots
+}
+
+pub fn locate_saves(roots: &[PathBuf]) -> Result<Vec<LocatedSave>, FESTError> {
+    let mut saves = vec![];
+
+    for root in roots {
+        let mut title_dirs = vec![];
+        find_title_dirs(root, LOCATE_DEPTH, &mut title_dirs)?;
+
+        for (title, title_dir) in title_dirs {
+            let mut files = vec![];
+            list_files(&title_dir.join("data"), &mut files);
+
+            saves.extend(files.into_iter().map(|path| {
+                let name = path.to_string_lossy().to_string();
+                let kind = fs::read(&path)
+                    .ok()
+                    .and_then(|raw| FESData::process_file_data(&raw, &name).ok())
+                    .map(|file_data| file_data.kind());
+
+                LocatedSave {
+                    slot: kind.and(SaveKind::slot_from_file_name(&name)),
+                    path,
+                    title,
+                    kind,
+                }
+            }));
+        }
+    }
+
+    Ok(saves)
+}
+
+fn find_title_dirs(
+    dir: &Path,
+    depth: usize,
+    title_dirs: &mut Vec<(Title, PathBuf)>,
+) -> Result<(), FESTError> {
+    if depth == 0 {
+        return Ok(());
+    }
+
+    for path in sorted_entries(dir)? {
+        if !path.is_dir() {
+            continue;
+        }
+
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+
+        if name == TITLE_HIGH {
+            for title_dir in sorted_entries(&path).unwrap_or_default() {
+                let low = title_dir
+                    .file_name()
+                    .map(|low| low.to_string_lossy().to_ascii_lowercase())
+                    .unwrap_or_default();
+
+                if let Some(title) = TITLES.into_iter().find(|title| title.low() == low) {
+                    title_dirs.push((title, title_dir));
+                }
+            }
+        } else {
+            // NOTE: Only the roots have to be readable, anything below them that isn't is just not searched
+            find_title_dirs(&path, depth - 1, title_dirs).ok();
+        }
+    }
+
+    Ok(())
+}
+
+fn list_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    if !dir.is_dir() {
+        return;
+    }
+
+    for path in sorted_entries(dir).unwrap_or_default() {
+        if path.is_dir() {
+            list_files(&path, files);
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+}
+
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>, FESTError> {
+    let mut entries = fs::read_dir(dir)
+        .map_err(|e| FESTError::ReadError(format!("'{}': {}", dir.display(), e)))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect::<Vec<PathBuf>>();
+
+    entries.sort();
+
+    Ok(entries)
+}
@@ -1,4 +1,5 @@
 use crate::constants::FESTError;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub fn to_uint32(bytes: &[u8], offset: usize) -> Result<u32, FESTError> {
     let bytes: [u8; 4] = bytes
@@ -13,3 +14,38 @@ pub fn to_uint32(bytes: &[u8], offset: usize) -> Result<u32, FESTError> {
 pub fn from_uint32(value: u32) -> Vec<u8> {
     value.to_le_bytes().to_vec()
 }
+
+// NOTE: Formats as "YYYYMMDDTHHMMSSZ" in UTC, so sorting the strings also sorts them in time
+pub fn timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+
+    // NOTE: Days to a civil date, from Howard Hinnant's "civil_from_days"
+    let days = days as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60
+    )
+}
@@ -0,0 +1,91 @@
+use crate::constants::FESTError;
+use std::{fmt, str::FromStr};
+
+// NOTE: Nothing in a save is known to tell which game wrote it (Every game has the same header layout, and no sizes or block counts were ever checked against real saves), so the game is only known when it is given, with "--game" or from the title ID of the directory "locate" found it in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameVariant {
+    Awakening,
+    Fates,
+    Echoes,
+}
+
+impl GameVariant {
+    pub const ALL: [GameVariant; 3] = [
+        GameVariant::Awakening,
+        GameVariant::Fates,
+        GameVariant::Echoes,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameVariant::Awakening => "Awakening",
+            GameVariant::Fates => "Fates",
+            GameVariant::Echoes => "Echoes",
+        }
+    }
+}
+
+impl fmt::Display for GameVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for GameVariant {
+    type Err = FESTError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        GameVariant::ALL
+            .into_iter()
+            .find(|variant| variant.name().eq_ignore_ascii_case(value))
+            .ok_or(FESTError::UnknownVariant(value.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FESData;
+
+    fn index_save() -> Vec<u8> {
+        let mut raw = vec![];
+        raw.extend(0x494E4445u32.to_le_bytes());
+        raw.extend(0u32.to_le_bytes());
+
+        raw
+    }
+
+    #[test]
+    fn games_are_only_known_when_given() {
+        let mut file_data = FESData::process_data(&index_save()).unwrap();
+        assert_eq!(file_data.variant(), None);
+
+        file_data.set_variant(GameVariant::Fates);
+        assert_eq!(file_data.variant(), Some(GameVariant::Fates));
+
+        // NOTE: The same game has to come back in both forms
+        let compressed = file_data.compress().unwrap();
+        assert_eq!(compressed.variant(), Some(GameVariant::Fates));
+        assert_eq!(
+            compressed.decompress().unwrap().variant(),
+            Some(GameVariant::Fates)
+        );
+    }
+
+    #[test]
+    fn names_parse_back() {
+        for variant in GameVariant::ALL {
+            assert_eq!(variant.to_string().parse::<GameVariant>().unwrap(), variant);
+            assert_eq!(
+                variant
+                    .name()
+                    .to_uppercase()
+                    .parse::<GameVariant>()
+                    .unwrap(),
+                variant
+            );
+        }
+
+        assert!("Radiant Dawn".parse::<GameVariant>().is_err());
+    }
+}
@@ -0,0 +1,314 @@
+use crate::constants::FESTError;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    pub no_clobber: bool,
+    pub sync: bool,
+}
+
+// NOTE: Data is written to a temporary file next to the target and only then moved over it, so a crash can't leave a half-written save behind
+pub fn write_atomic(path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FESTError> {
+    write_atomic_with(path, data, options, |from, to| fs::hard_link(from, to))
+}
+
+fn write_atomic_with<L>(
+    path: &Path,
+    data: &[u8],
+    options: WriteOptions,
+    link: L,
+) -> Result<(), FESTError>
+where
+    L: Fn(&Path, &Path) -> io::Result<()>,
+{
+    match fs::symlink_metadata(path) {
+        // NOTE: Renaming over a link would replace the link instead of the file it points to, so the file is written as if it was given instead
+        Ok(metadata) if metadata.file_type().is_symlink() => {
+            let real_path = fs::canonicalize(path)
+                .map_err(|e| FESTError::WriteError(format!("'{}': {}", path.display(), e)))?;
+
+            return write_atomic_with(&real_path, data, options, link);
+        }
+        // NOTE: Devices and FIFOs (Like "/dev/null") can't be replaced, only written into
+        Ok(metadata) if !metadata.is_file() && !metadata.is_dir() => {
+            if options.no_clobber {
+                return Err(FESTError::FileExists(path.display().to_string()));
+            }
+
+            return OpenOptions::new()
+                .write(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(data))
+                .map_err(|e| FESTError::WriteError(e.to_string()));
+        }
+        _ => {}
+    }
+
+    let temp_path = temp_path(path);
+
+    let result = write_temp(&temp_path, data, options).and_then(|_| {
+        match options.no_clobber {
+            // NOTE: Linking fails if the target exists, unlike renaming, so nothing can sneak in between checking and writing
+            true => match link(&temp_path, path) {
+                Ok(()) => Ok(()),
+                // NOTE: FAT32 and exFAT, which SD cards use, have no hard links, so there the file is created only if it doesn't exist and written in place
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        ErrorKind::Unsupported | ErrorKind::PermissionDenied
+                    ) =>
+                {
+                    write_new(path, data, options)
+                }
+                Err(e) => Err(exists_error(path, e)),
+            },
+            false => {
+                // NOTE: Renaming replaces the file, so whatever permissions it had have to be carried over
+                if let Ok(metadata) = fs::metadata(path) {
+                    fs::set_permissions(&temp_path, metadata.permissions())
+                        .map_err(|e| FESTError::WriteError(e.to_string()))?;
+                }
+
+                fs::rename(&temp_path, path).map_err(|e| FESTError::WriteError(e.to_string()))
+            }
+        }
+    });
+
+    let _ = fs::remove_file(&temp_path);
+
+    result
+}
+
+// NOTE: Same as "write_atomic" but for a whole directory of files, which is built next to the target and swapped in with the previous one only once every file is written
+pub fn write_dir_atomic(
+    path: &Path,
+    files: &[(PathBuf, &[u8])],
+    options: WriteOptions,
+) -> Result<(), FESTError> {
+    if options.no_clobber && path.exists() {
+        return Err(FESTError::FileExists(path.display().to_string()));
+    }
+
+    let temp_dir = temp_path(path);
+
+    let result = write_temp_dir(&temp_dir, files, options).and_then(|_| {
+        if !path.exists() {
+            return fs::rename(&temp_dir, path).map_err(|e| FESTError::WriteError(e.to_string()));
+        }
+
+        let old_dir = temp_path(path);
+        fs::rename(path, &old_dir).map_err(|e| FESTError::WriteError(e.to_string()))?;
+
+        match fs::rename(&temp_dir, path) {
+            Ok(()) => {
+                let _ = fs::remove_dir_all(&old_dir);
+                Ok(())
+            }
+            Err(e) => {
+                let _ = fs::rename(&old_dir, path);
+                Err(FESTError::WriteError(e.to_string()))
+            }
+        }
+    });
+
+    let _ = fs::remove_dir_all(&temp_dir);
+
+    result
+}
+
+fn write_temp_dir(
+    temp_dir: &Path,
+    files: &[(PathBuf, &[u8])],
+    options: WriteOptions,
+) -> Result<(), FESTError> {
+    for (relative, data) in files {
+        let path = temp_dir.join(relative);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| FESTError::WriteError(e.to_string()))?;
+        }
+
+        write_temp(&path, data, options)?;
+    }
+
+    // NOTE: An empty set still has to give an empty directory
+    fs::create_dir_all(temp_dir).map_err(|e| FESTError::WriteError(e.to_string()))
+}
+
+fn write_new(path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FESTError> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(|e| exists_error(path, e))?;
+
+    let result = file.write_all(data).and_then(|_| match options.sync {
+        true => file.sync_all(),
+        false => Ok(()),
+    });
+
+    // NOTE: A half-written file is worse than none, since it would be refused as existing next time
+    if let Err(e) = result {
+        let _ = fs::remove_file(path);
+        return Err(FESTError::WriteError(e.to_string()));
+    }
+
+    Ok(())
+}
+
+fn exists_error(path: &Path, error: io::Error) -> FESTError {
+    match error.kind() {
+        ErrorKind::AlreadyExists => FESTError::FileExists(path.display().to_string()),
+        _ => FESTError::WriteError(error.to_string()),
+    }
+}
+
+fn write_temp(temp_path: &Path, data: &[u8], options: WriteOptions) -> Result<(), FESTError> {
+    let mut file = File::create(temp_path).map_err(|e| FESTError::WriteError(e.to_string()))?;
+
+    file.write_all(data)
+        .map_err(|e| FESTError::WriteError(e.to_string()))?;
+
+    if options.sync {
+        file.sync_all()
+            .map_err(|e| FESTError::WriteError(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        name,
+        process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    // NOTE: A fresh directory for each test, as they run at the same time
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("fest-write-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    fn names(dir: &Path) -> Vec<String> {
+        let mut names = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<String>>();
+        names.sort();
+
+        names
+    }
+
+    const NO_CLOBBER: WriteOptions = WriteOptions {
+        no_clobber: true,
+        sync: false,
+    };
+
+    #[test]
+    fn no_clobber_keeps_existing_files() {
+        let dir = test_dir("no-clobber");
+        let path = dir.join("Chapter0");
+
+        write_atomic(&path, b"first", NO_CLOBBER).unwrap();
+        assert!(matches!(
+            write_atomic(&path, b"second", NO_CLOBBER),
+            Err(FESTError::FileExists(_))
+        ));
+        assert_eq!(fs::read(&path).unwrap(), b"first");
+        assert_eq!(names(&dir), ["Chapter0"]);
+
+        write_atomic(&path, b"second", WriteOptions::default()).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert_eq!(names(&dir), ["Chapter0"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn no_clobber_works_without_hard_links() {
+        let dir = test_dir("no-links");
+        let path = dir.join("Chapter0");
+        let unsupported = |_: &Path, _: &Path| Err(io::Error::from(ErrorKind::Unsupported));
+
+        write_atomic_with(&path, b"first", NO_CLOBBER, unsupported).unwrap();
+        assert!(matches!(
+            write_atomic_with(&path, b"second", NO_CLOBBER, unsupported),
+            Err(FESTError::FileExists(_))
+        ));
+        assert_eq!(fs::read(&path).unwrap(), b"first");
+        assert_eq!(names(&dir), ["Chapter0"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn overwriting_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = test_dir("permissions");
+        let path = dir.join("Chapter0");
+
+        fs::write(&path, b"first").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        write_atomic(&path, b"second", WriteOptions::default()).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o640
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_are_written_through() {
+        let dir = test_dir("links");
+        let real_path = dir.join("real");
+        let link_path = dir.join("link");
+
+        fs::write(&real_path, b"first").unwrap();
+        std::os::unix::fs::symlink(&real_path, &link_path).unwrap();
+
+        assert!(matches!(
+            write_atomic(&link_path, b"second", NO_CLOBBER),
+            Err(FESTError::FileExists(_))
+        ));
+
+        write_atomic(&link_path, b"second", WriteOptions::default()).unwrap();
+        assert!(fs::symlink_metadata(&link_path)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read(&real_path).unwrap(), b"second");
+        assert_eq!(names(&dir), ["link", "real"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,111 @@
+use crate::constants::FESTError;
+use crate::FESData;
+use std::io::{Read, Seek, Write};
+use zip::{result::ZipError, ZipArchive, ZipWriter};
+
+#[derive(Debug, Clone)]
+pub struct ZippedSave {
+    pub name: String,
+    pub data: FESData,
+}
+
+// NOTE: Every entry that "probe" takes as a save (Like "FESData::process_file_data", given the data and name of each entry), in the order they are stored, anything else is left out
+pub fn read_zip<R, P>(reader: R, probe: P) -> Result<Vec<ZippedSave>, FESTError>
+where
+    R: Read + Seek,
+    P: Fn(&[u8], &str) -> Result<FESData, FESTError>,
+{
+    let mut archive = ZipArchive::new(reader).map_err(zip_error)?;
+    let mut saves = vec![];
+
+    for i in 0..archive.len() {
+        if let Some(save) = read_entry(&mut archive, i, &probe)? {
+            saves.push(save);
+        }
+    }
+
+    Ok(saves)
+}
+
+// NOTE: Saves are given to "transform", and the ones it gives back replace them in the new archive with the same name and options, while every other entry (And the saves it gives nothing for) is copied without being decompressed again, so it stays byte for byte the same
+pub fn rewrite_zip<R, W, P, F>(
+    reader: R,
+    writer: W,
+    probe: P,
+    mut transform: F,
+) -> Result<W, FESTError>
+where
+    R: Read + Seek,
+    W: Write + Seek,
+    P: Fn(&[u8], &str) -> Result<FESData, FESTError>,
+    F: FnMut(&ZippedSave) -> Result<Option<FESData>, FESTError>,
+{
+    let mut archive = ZipArchive::new(reader).map_err(zip_error)?;
+    let mut zip_writer = ZipWriter::new(writer);
+
+    zip_writer
+        .set_raw_comment(archive.comment().to_vec().into_boxed_slice())
+        .map_err(zip_error)?;
+
+    for i in 0..archive.len() {
+        let replacement = match read_entry(&mut archive, i, &probe)? {
+            Some(save) => transform(&save)?,
+            None => None,
+        };
+
+        match replacement {
+            Some(data) => {
+                let file = archive.by_index_raw(i).map_err(zip_error)?;
+
+                zip_writer
+                    .start_file(file.name(), file.options())
+                    .map_err(zip_error)?;
+                zip_writer
+                    .write_all(&data.raw)
+                    .map_err(|e| FESTError::WriteError(e.to_string()))?;
+            }
+            None => {
+                let file = archive.by_index_raw(i).map_err(zip_error)?;
+
+                zip_writer.raw_copy_file(file).map_err(zip_error)?;
+            }
+        }
+    }
+
+    zip_writer.finish().map_err(zip_error)
+}
+
+fn read_entry<R, P>(
+    archive: &mut ZipArchive<R>,
+    index: usize,
+    probe: &P,
+) -> Result<Option<ZippedSave>, FESTError>
+where
+    R: Read + Seek,
+    P: Fn(&[u8], &str) -> Result<FESData, FESTError>,
+{
+    // NOTE: Entries that can't be decompressed with the methods built in can't be saves this library knows anyway
+    let mut file = match archive.by_index(index) {
+        Err(ZipError::UnsupportedArchive(_) | ZipError::CompressionMethodNotSupported(_)) => {
+            return Ok(None)
+        }
+        file => file.map_err(zip_error)?,
+    };
+
+    if !file.is_file() {
+        return Ok(None);
+    }
+
+    let name = file.name().to_string();
+    let mut raw = vec![];
+    file.read_to_end(&mut raw)
+        .map_err(|e| FESTError::ReadError(format!("'{}': {}", name, e)))?;
+
+    Ok(probe(&raw, &name)
+        .ok()
+        .map(|data| ZippedSave { name, data }))
+}
+
+fn zip_error(error: ZipError) -> FESTError {
+    FESTError::ZipError(error.to_string())
+}
@@ -0,0 +1,104 @@
+use fest::{FESData, SaveIndex};
+
+fn chapter_save() -> Vec<u8> {
+    let mut raw = (0..0xC0).map(|byte| byte as u8).collect::<Vec<u8>>();
+    let blocks: [(u32, Vec<u8>); 2] = [
+        (0x554E4954, b"Chrom Lissa Frederick".repeat(8)),
+        (0x434F4E56, (0..0x80).map(|byte| (byte * 7) as u8).collect()),
+    ];
+
+    raw.extend(0x494E4445u32.to_le_bytes());
+    raw.extend((blocks.len() as u32).to_le_bytes());
+
+    let mut offset = 8 + blocks.len() * 12;
+    for (id, data) in &blocks {
+        raw.extend(id.to_le_bytes());
+        raw.extend((offset as u32).to_le_bytes());
+        raw.extend((data.len() as u32).to_le_bytes());
+
+        offset += data.len();
+    }
+
+    for (_, data) in blocks {
+        raw.extend(data);
+    }
+
+    raw
+}
+
+fn original_save() -> Vec<u8> {
+    let mut raw = FESData::process_data(&chapter_save())
+        .unwrap()
+        .compress()
+        .unwrap()
+        .raw;
+
+    raw[0xC4..0xC8].copy_from_slice(&5u32.to_le_bytes()); // NOTE: Any version other than the one "compress" writes by default
+    raw.extend([0xAA, 0xBB]);
+
+    raw
+}
+
+#[test]
+fn untouched_save_is_reproduced() {
+    let original = original_save();
+
+    let decompressed = FESData::process_data(&original)
+        .unwrap()
+        .decompress()
+        .unwrap();
+    assert_eq!(decompressed.raw, chapter_save());
+    assert_eq!(decompressed.comp_header().unwrap().version, 5);
+
+    let compressed = decompressed.compress().unwrap();
+    assert_eq!(compressed.raw, original);
+}
+
+#[test]
+fn edited_save_is_still_valid() {
+    let mut decompressed = FESData::process_data(&original_save())
+        .unwrap()
+        .decompress()
+        .unwrap();
+
+    let mut index = decompressed.index().unwrap();
+    index.replace(0x554E4954, vec![0x01; 0x30]).unwrap(); // NOTE: 0x01 appears nowhere in the original data, so the original tree has no code for it
+    decompressed.set_index(&index).unwrap();
+
+    let edited = decompressed.raw.clone();
+    let compressed = decompressed.compress().unwrap();
+    assert_eq!(compressed.comp_header().unwrap().version, 5);
+
+    let decompressed = FESData::process_data(&compressed.raw)
+        .unwrap()
+        .decompress()
+        .unwrap();
+    assert_eq!(decompressed.raw, edited);
+    assert_eq!(
+        SaveIndex::parse(&edited[0xC0..]).unwrap().block(0x554E4954),
+        Some(&[0x01; 0x30][..])
+    );
+}
+
+#[test]
+fn edited_save_drops_old_padding() {
+    let mut decompressed = FESData::process_data(&original_save())
+        .unwrap()
+        .decompress()
+        .unwrap();
+
+    let mut index = decompressed.index().unwrap();
+    index.replace(0x554E4954, b"Lissa Chrom".repeat(4)).unwrap(); // NOTE: Only symbols the original tree has a code for
+    decompressed.set_index(&index).unwrap();
+
+    let edited = decompressed.raw.clone();
+    let compressed = decompressed.compress().unwrap();
+    assert!(!compressed.raw.ends_with(&[0xAA, 0xBB]));
+    assert!(compressed.verify().unwrap());
+
+    let decompressed = FESData::process_data(&compressed.raw)
+        .unwrap()
+        .decompress()
+        .unwrap();
+    assert_eq!(decompressed.raw, edited);
+}